@@ -0,0 +1,120 @@
+use log::debug;
+use serde::Serialize;
+
+use crate::audio::DecodedAudio;
+
+/// Levels below this are reported as this, so silence stays finite in JSON.
+const FLOOR_DB: f32 = -120.0;
+
+/// Samples at or above this magnitude count as clipped.
+const CLIPPING_LEVEL: f32 = 0.999;
+
+/// Frames quieter than this count as silence.
+const SILENCE_THRESHOLD_DB: f32 = -50.0;
+
+/// Analysis frame length for the silence ratio and SNR estimate.
+const FRAME_MS: u32 = 20;
+
+/// Diagnostics of an uploaded recording, for telling bad audio apart from
+/// bad transcription.
+#[derive(Clone, Debug, Serialize)]
+pub struct AudioInfo {
+    pub duration_seconds: f64,
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// Container and sample format the upload was decoded from.
+    pub format: String,
+    pub peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    /// Share of samples at full scale.
+    pub clipping_ratio: f32,
+    /// Loudest fifth of frames against the quietest fifth.
+    pub snr_db: f32,
+    /// Share of frames below -50 dBFS.
+    pub silence_ratio: f32,
+}
+
+impl AudioInfo {
+    pub fn analyze(audio: &DecodedAudio) -> Self {
+        let samples = &audio.samples;
+        let channels = audio.channels.max(1);
+        let frames = samples.len() / channels;
+
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        let mean_square = if samples.is_empty() {
+            0.0
+        } else {
+            samples.iter().map(|&s| s as f64 * s as f64).sum::<f64>() / samples.len() as f64
+        };
+        let clipped = samples.iter().filter(|s| s.abs() >= CLIPPING_LEVEL).count();
+
+        let frame_len = (audio.sample_rate * FRAME_MS / 1000).max(1) as usize * channels;
+        let mut frame_powers = frame_powers(samples, frame_len);
+        let silent_frames = frame_powers
+            .iter()
+            .filter(|&&power| power_to_db(power) < SILENCE_THRESHOLD_DB)
+            .count();
+
+        let info = Self {
+            duration_seconds: if audio.sample_rate > 0 {
+                frames as f64 / audio.sample_rate as f64
+            } else {
+                0.0
+            },
+            sample_rate: audio.sample_rate,
+            channels: audio.channels,
+            format: audio.format.clone(),
+            peak_dbfs: amplitude_to_db(peak),
+            rms_dbfs: power_to_db(mean_square as f32),
+            clipping_ratio: ratio(clipped, samples.len()),
+            snr_db: estimate_snr_db(&mut frame_powers),
+            silence_ratio: ratio(silent_frames, frame_powers.len()),
+        };
+
+        debug!("Audio info: {info:?}");
+
+        info
+    }
+}
+
+/// Mean power of every whole `frame_len`-sample frame.
+pub fn frame_powers(samples: &[f32], frame_len: usize) -> Vec<f32> {
+    samples
+        .chunks_exact(frame_len.max(1))
+        .map(|frame| frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Rough signal-to-noise ratio in dB, comparing the loudest fifth of
+/// frames against the quietest fifth. Sorts `frame_powers` in place.
+pub fn estimate_snr_db(frame_powers: &mut [f32]) -> f32 {
+    if frame_powers.is_empty() {
+        return 0.0;
+    }
+    frame_powers.sort_by(|a, b| a.total_cmp(b));
+
+    let share = (frame_powers.len() / 5).max(1);
+    let noise = frame_powers[..share].iter().sum::<f32>() / share as f32;
+    let loud = frame_powers[frame_powers.len() - share..]
+        .iter()
+        .sum::<f32>()
+        / share as f32;
+
+    power_to_db((loud - noise).max(0.0)) - power_to_db(noise)
+}
+
+pub fn amplitude_to_db(amplitude: f32) -> f32 {
+    (20.0 * amplitude.log10()).max(FLOOR_DB)
+}
+
+pub fn power_to_db(power: f32) -> f32 {
+    (10.0 * power.log10()).max(FLOOR_DB)
+}
+
+fn ratio(count: usize, total: usize) -> f32 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f32 / total as f32
+    }
+}
@@ -0,0 +1,161 @@
+use log::{debug, error, warn};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{CodecParameters, DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::audio::DecodedAudio;
+
+/// Decodes a compressed audio file with symphonia. `extension` is passed to
+/// the format probe as a hint, content sniffing still has the final word.
+pub fn decode_compressed(audio_bytes: &[u8], extension: &str) -> Result<DecodedAudio, String> {
+    debug!(
+        "Decoding {} bytes of compressed audio (hint: {extension})",
+        audio_bytes.len()
+    );
+
+    let source = std::io::Cursor::new(audio_bytes.to_vec());
+    let stream = MediaSourceStream::new(Box::new(source), Default::default());
+
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| {
+            error!("Failed to probe {extension} audio: {e}");
+            format!("Unrecognized {extension} audio: {e}")
+        })?;
+    let mut reader = probed.format;
+
+    let track = reader
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("No audio track found in {extension} file"))?;
+    let track_id = track.id;
+    let codec_params = track.codec_params.clone();
+
+    // symphonia has no Opus decoder, Ogg/WebM Opus packets go to libopus
+    if codec_params.codec == CODEC_TYPE_OPUS {
+        return decode_opus(reader.as_mut(), track_id, &codec_params, extension);
+    }
+
+    decode_track(reader.as_mut(), track_id, &codec_params, extension)
+}
+
+fn decode_track(
+    reader: &mut dyn FormatReader,
+    track_id: u32,
+    codec_params: &CodecParameters,
+    extension: &str,
+) -> Result<DecodedAudio, String> {
+    let mut decoder = symphonia::default::get_codecs()
+        .make(codec_params, &DecoderOptions::default())
+        .map_err(|e| {
+            error!("Unsupported codec in {extension} file: {e}");
+            format!("Unsupported codec in {extension} file: {e}")
+        })?;
+
+    let mut samples = Vec::new();
+    let mut sample_rate = codec_params.sample_rate.unwrap_or(0);
+    let mut channels = codec_params.channels.map(|c| c.count()).unwrap_or(0);
+    let mut sample_buffer: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => {
+                error!("Failed to read {extension} packet: {e}");
+                return Err(format!("Failed to read {extension} audio: {e}"));
+            }
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(e)) => {
+                // A corrupt frame should not cost the whole file
+                warn!("Skipping undecodable {extension} packet: {e}");
+                continue;
+            }
+            Err(e) => {
+                error!("Failed to decode {extension} packet: {e}");
+                return Err(format!("Failed to decode {extension} audio: {e}"));
+            }
+        };
+
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        channels = spec.channels.count();
+
+        let buffer = match sample_buffer.as_mut() {
+            Some(buffer) if buffer.capacity() >= decoded.capacity() * channels => buffer,
+            _ => sample_buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+
+    if sample_rate == 0 || channels == 0 {
+        return Err(format!(
+            "Could not determine sample rate or channel count of {extension} audio"
+        ));
+    }
+
+    let bits = codec_params
+        .bits_per_sample
+        .map(|b| format!("/{b}bit"))
+        .unwrap_or_default();
+
+    debug!(
+        "Decoded {extension} audio: {} samples, {}Hz, {} channels",
+        samples.len(),
+        sample_rate,
+        channels
+    );
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate,
+        channels,
+        format: format!("{extension}{bits}"),
+    })
+}
+
+#[cfg(feature = "opus")]
+fn decode_opus(
+    reader: &mut dyn FormatReader,
+    track_id: u32,
+    codec_params: &CodecParameters,
+    extension: &str,
+) -> Result<DecodedAudio, String> {
+    crate::audio::opus::decode_opus_track(reader, track_id, codec_params, extension)
+}
+
+#[cfg(not(feature = "opus"))]
+fn decode_opus(
+    _reader: &mut dyn FormatReader,
+    _track_id: u32,
+    _codec_params: &CodecParameters,
+    extension: &str,
+) -> Result<DecodedAudio, String> {
+    error!("Opus audio in {extension} file, but Opus support is not compiled in");
+    Err(format!(
+        "Opus audio in {extension} file is not supported by this build (enable the `opus` feature)"
+    ))
+}
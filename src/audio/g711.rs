@@ -0,0 +1,126 @@
+//! ITU-T G.711 companding, as used by telephony systems for 8-bit
+//! mu-law (North America/Japan) and A-law (Europe) audio.
+
+const MULAW_BIAS: i32 = 0x84;
+const MULAW_CLIP: i32 = 32635;
+
+pub fn mulaw_to_linear(byte: u8) -> i16 {
+    let byte = !byte;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0F) as i32;
+    let magnitude = (((mantissa << 3) + MULAW_BIAS) << exponent) - MULAW_BIAS;
+    if byte & 0x80 != 0 {
+        -magnitude as i16
+    } else {
+        magnitude as i16
+    }
+}
+
+pub fn alaw_to_linear(byte: u8) -> i16 {
+    let byte = byte ^ 0x55;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0F) as i32;
+    let magnitude = if exponent == 0 {
+        (mantissa << 4) + 8
+    } else {
+        ((mantissa << 4) + 0x108) << (exponent - 1)
+    };
+    // A-law sets the sign bit for positive samples
+    if byte & 0x80 != 0 {
+        magnitude as i16
+    } else {
+        -magnitude as i16
+    }
+}
+
+pub fn linear_to_mulaw(sample: i16) -> u8 {
+    let mut magnitude = sample as i32;
+    let sign = if magnitude < 0 {
+        magnitude = -magnitude;
+        0x80
+    } else {
+        0x00
+    };
+    magnitude = magnitude.min(MULAW_CLIP) + MULAW_BIAS;
+
+    let exponent = ((magnitude >> 7) as u32).ilog2().min(7) as i32;
+    let mantissa = (magnitude >> (exponent + 3)) & 0x0F;
+    !((sign | (exponent << 4) | mantissa) as u8)
+}
+
+pub fn linear_to_alaw(sample: i16) -> u8 {
+    let mut magnitude = sample as i32;
+    let sign = if magnitude >= 0 {
+        0x80
+    } else {
+        magnitude = -magnitude - 1;
+        0x00
+    };
+
+    let (exponent, mantissa) = if magnitude < 256 {
+        (0, (magnitude >> 4) & 0x0F)
+    } else {
+        let exponent = (((magnitude >> 8) as u32).ilog2() + 1).min(7) as i32;
+        (exponent, (magnitude >> (exponent + 3)) & 0x0F)
+    };
+    ((sign | (exponent << 4) | mantissa) as u8) ^ 0x55
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_mulaw_reference_values() {
+        assert_eq!(mulaw_to_linear(0xFF), 0);
+        assert_eq!(mulaw_to_linear(0x7F), 0);
+        assert_eq!(mulaw_to_linear(0x80), 32124);
+        assert_eq!(mulaw_to_linear(0x00), -32124);
+        assert_eq!(mulaw_to_linear(0xFE), 8);
+        assert_eq!(mulaw_to_linear(0x7E), -8);
+    }
+
+    #[test]
+    fn decodes_alaw_reference_values() {
+        assert_eq!(alaw_to_linear(0xD5), 8);
+        assert_eq!(alaw_to_linear(0x55), -8);
+        assert_eq!(alaw_to_linear(0xAA), 32256);
+        assert_eq!(alaw_to_linear(0x2A), -32256);
+    }
+
+    #[test]
+    fn encodes_silence_and_clips_extremes() {
+        assert_eq!(linear_to_mulaw(0), 0xFF);
+        assert_eq!(linear_to_mulaw(i16::MAX), 0x80);
+        assert_eq!(linear_to_mulaw(i16::MIN), 0x00);
+        assert_eq!(linear_to_alaw(0), 0xD5);
+        assert_eq!(linear_to_alaw(i16::MAX), 0xAA);
+        assert_eq!(linear_to_alaw(i16::MIN), 0x2A);
+    }
+
+    #[test]
+    fn mulaw_round_trips_every_code() {
+        for byte in 0..=u8::MAX {
+            // 0x7F is negative zero, which encodes back as positive zero
+            if byte == 0x7F {
+                continue;
+            }
+            assert_eq!(
+                linear_to_mulaw(mulaw_to_linear(byte)),
+                byte,
+                "code {byte:#04X}"
+            );
+        }
+    }
+
+    #[test]
+    fn alaw_round_trips_every_code() {
+        for byte in 0..=u8::MAX {
+            assert_eq!(
+                linear_to_alaw(alaw_to_linear(byte)),
+                byte,
+                "code {byte:#04X}"
+            );
+        }
+    }
+}
@@ -0,0 +1,618 @@
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use log::{debug, error};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::ClientConfig;
+
+pub mod analysis;
+pub mod codec;
+pub mod g711;
+#[cfg(feature = "opus")]
+pub mod opus;
+pub mod wav;
+
+/// Format used for uploads that carry no container header.
+#[derive(Clone, Copy, Debug)]
+pub struct RawPcmFormat {
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub encoding: SampleEncoding,
+}
+
+impl Default for RawPcmFormat {
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            channels: 1,
+            encoding: SampleEncoding::S16Le,
+        }
+    }
+}
+
+pub struct DecodedAudio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub format: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioContainer {
+    Wav,
+    Flac,
+    Mp3,
+    Aac,
+    Mp4,
+    Ogg,
+    WebM,
+    RawPcm,
+}
+
+impl AudioContainer {
+    /// Detects the container from its magic bytes, falling back to the
+    /// uploaded filename for streams without a reliable signature.
+    pub fn detect(audio_bytes: &[u8], filename: Option<&str>) -> Self {
+        Self::from_magic(audio_bytes)
+            .or_else(|| filename.and_then(Self::from_filename))
+            .unwrap_or(Self::RawPcm)
+    }
+
+    fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if wav::is_wav(bytes) {
+            return Some(Self::Wav);
+        }
+        if bytes.starts_with(b"fLaC") {
+            return Some(Self::Flac);
+        }
+        if bytes.starts_with(b"OggS") {
+            return Some(Self::Ogg);
+        }
+        // EBML header, shared by WebM and Matroska
+        if bytes.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+            return Some(Self::WebM);
+        }
+        if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+            return Some(Self::Mp4);
+        }
+        if bytes.starts_with(b"ID3") || is_mpeg_audio_frame(bytes) {
+            return Some(Self::Mp3);
+        }
+        if is_adts_frame(bytes) {
+            return Some(Self::Aac);
+        }
+        None
+    }
+
+    /// Only compressed formats are trusted by extension. WAV and FLAC are
+    /// always identified by their header, so a raw recording named
+    /// `something.wav` still decodes as raw PCM.
+    fn from_filename(filename: &str) -> Option<Self> {
+        let extension = std::path::Path::new(filename)
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "mp3" => Some(Self::Mp3),
+            "aac" => Some(Self::Aac),
+            "m4a" | "mp4" | "m4b" => Some(Self::Mp4),
+            "ogg" | "oga" | "opus" => Some(Self::Ogg),
+            "webm" | "mka" | "mkv" => Some(Self::WebM),
+            _ => None,
+        }
+    }
+
+    /// Extension handed to symphonia as a probe hint.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+            Self::Mp3 => "mp3",
+            Self::Aac => "aac",
+            Self::Mp4 => "m4a",
+            Self::Ogg => "ogg",
+            Self::WebM => "webm",
+            Self::RawPcm => "pcm",
+        }
+    }
+}
+
+/// Bitrates in kbit/s of MPEG-1 and MPEG-2/2.5 layer III, by bitrate index.
+const MP3_BITRATES_V1: [u32; 15] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+];
+const MP3_BITRATES_V2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// Checks for two consecutive MPEG-1/2 layer III frame headers. A single
+/// sync word is too weak: raw s16le PCM starting with a sample such as
+/// 0xFBFF looks like a valid header, but never has a second one where the
+/// first frame ends.
+fn is_mpeg_audio_frame(bytes: &[u8]) -> bool {
+    let Some(frame_len) = mpeg_audio_frame_len(bytes) else {
+        return false;
+    };
+    match bytes.get(frame_len..) {
+        // A stream holding a single frame has nothing more to check
+        Some([]) => true,
+        Some(next) => mpeg_audio_frame_len(next).is_some() && next[1] == bytes[1],
+        None => false,
+    }
+}
+
+/// Length in bytes of the MPEG layer III frame starting at `bytes`, if it
+/// starts with a valid frame header.
+fn mpeg_audio_frame_len(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() < 4 || bytes[0] != 0xFF || bytes[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+    let version = (bytes[1] >> 3) & 0x03;
+    let layer = (bytes[1] >> 1) & 0x03;
+    let bitrate_index = (bytes[2] >> 4) as usize;
+    let sample_rate_index = ((bytes[2] >> 2) & 0x03) as usize;
+    let padding = ((bytes[2] >> 1) & 0x01) as usize;
+    if version == 0x01
+        || layer != 0x01
+        || bitrate_index == 0
+        || bitrate_index == 0x0F
+        || sample_rate_index == 0x03
+    {
+        return None;
+    }
+
+    let base_rate = [44100, 48000, 32000][sample_rate_index];
+    // MPEG-1 frames hold 1152 samples, MPEG-2 and 2.5 frames 576
+    let (bitrate, sample_rate, coefficient) = match version {
+        0x03 => (MP3_BITRATES_V1[bitrate_index], base_rate, 144),
+        0x02 => (MP3_BITRATES_V2[bitrate_index], base_rate / 2, 72),
+        _ => (MP3_BITRATES_V2[bitrate_index], base_rate / 4, 72),
+    };
+    Some((coefficient * bitrate * 1000 / sample_rate) as usize + padding)
+}
+
+/// Checks for two consecutive ADTS headers (12-bit sync word, layer 0 and a
+/// valid sampling frequency index), for the same reason as
+/// `is_mpeg_audio_frame`.
+fn is_adts_frame(bytes: &[u8]) -> bool {
+    let Some(frame_len) = adts_frame_len(bytes) else {
+        return false;
+    };
+    match bytes.get(frame_len..) {
+        Some([]) => true,
+        Some(next) => adts_frame_len(next).is_some() && (next[2] >> 2) == (bytes[2] >> 2),
+        None => false,
+    }
+}
+
+/// Length in bytes of the ADTS frame starting at `bytes`, header included.
+fn adts_frame_len(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() < 7
+        || bytes[0] != 0xFF
+        || bytes[1] & 0xF6 != 0xF0
+        || (bytes[2] >> 2) & 0x0F >= 13
+    {
+        return None;
+    }
+    let frame_len =
+        ((bytes[3] as usize & 0x03) << 11) | ((bytes[4] as usize) << 3) | (bytes[5] as usize >> 5);
+    (frame_len >= 7).then_some(frame_len)
+}
+
+/// Decodes an uploaded audio payload. Containers that describe their own
+/// format are decoded from their header; anything else is treated as
+/// headerless PCM described by `raw_format`.
+pub fn decode_audio(
+    audio_bytes: &[u8],
+    filename: Option<&str>,
+    raw_format: &RawPcmFormat,
+) -> Result<DecodedAudio, String> {
+    let container = AudioContainer::detect(audio_bytes, filename);
+    debug!("Detected audio container: {container:?} (filename: {filename:?})");
+
+    match container {
+        AudioContainer::Wav => decode_wav(audio_bytes),
+        AudioContainer::Flac
+        | AudioContainer::Mp3
+        | AudioContainer::Aac
+        | AudioContainer::Mp4
+        | AudioContainer::Ogg
+        | AudioContainer::WebM => codec::decode_compressed(audio_bytes, container.extension()),
+        AudioContainer::RawPcm => decode_raw_pcm(audio_bytes, raw_format),
+    }
+}
+
+fn decode_raw_pcm(audio_bytes: &[u8], raw_format: &RawPcmFormat) -> Result<DecodedAudio, String> {
+    debug!(
+        "No container header detected, treating {} bytes as raw PCM: {}Hz, {} channels, {}",
+        audio_bytes.len(),
+        raw_format.sample_rate,
+        raw_format.channels,
+        raw_format.encoding
+    );
+
+    let samples = convert_audio_bytes_to_samples(audio_bytes, raw_format.encoding)?;
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: raw_format.sample_rate,
+        channels: raw_format.channels,
+        format: format!("raw/{}", raw_format.encoding),
+    })
+}
+
+fn decode_wav(audio_bytes: &[u8]) -> Result<DecodedAudio, String> {
+    let wav = wav::parse_wav(audio_bytes).map_err(|e| {
+        error!("Failed to parse WAV header: {e}");
+        e
+    })?;
+    let format = &wav.format;
+
+    let bits = format.container_bits();
+    let encoding = SampleEncoding::for_wav(format.format_tag, bits).ok_or_else(|| {
+        error!(
+            "Unsupported WAV format: tag 0x{:04X}, {} bits",
+            format.format_tag, bits
+        );
+        format!(
+            "Unsupported WAV format: tag 0x{:04X}, {} bits",
+            format.format_tag, bits
+        )
+    })?;
+    let samples = convert_audio_bytes_to_samples(wav.data, encoding)?;
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: format.sample_rate,
+        channels: format.channels,
+        format: format!("wav/{encoding}"),
+    })
+}
+
+/// Layout of a single sample in headerless PCM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleEncoding {
+    U8,
+    S16Le,
+    S16Be,
+    S24Le,
+    S24Be,
+    S32Le,
+    S32Be,
+    F32Le,
+    F32Be,
+    F64Le,
+    F64Be,
+    MuLaw,
+    ALaw,
+}
+
+impl SampleEncoding {
+    pub const ALL: [SampleEncoding; 13] = [
+        Self::U8,
+        Self::S16Le,
+        Self::S16Be,
+        Self::S24Le,
+        Self::S24Be,
+        Self::S32Le,
+        Self::S32Be,
+        Self::F32Le,
+        Self::F32Be,
+        Self::F64Le,
+        Self::F64Be,
+        Self::MuLaw,
+        Self::ALaw,
+    ];
+
+    /// Encoding implied by the legacy `bit_depth` parameter: unsigned for
+    /// 8-bit, signed little-endian integer otherwise.
+    pub fn from_bit_depth(bit_depth: u8) -> Option<Self> {
+        match bit_depth {
+            8 => Some(Self::U8),
+            16 => Some(Self::S16Le),
+            24 => Some(Self::S24Le),
+            32 => Some(Self::S32Le),
+            _ => None,
+        }
+    }
+
+    fn for_wav(format_tag: u16, bits: u16) -> Option<Self> {
+        match (format_tag, bits) {
+            (wav::WAVE_FORMAT_PCM, 8) => Some(Self::U8),
+            (wav::WAVE_FORMAT_PCM, 16) => Some(Self::S16Le),
+            (wav::WAVE_FORMAT_PCM, 24) => Some(Self::S24Le),
+            (wav::WAVE_FORMAT_PCM, 32) => Some(Self::S32Le),
+            (wav::WAVE_FORMAT_IEEE_FLOAT, 32) => Some(Self::F32Le),
+            (wav::WAVE_FORMAT_IEEE_FLOAT, 64) => Some(Self::F64Le),
+            (wav::WAVE_FORMAT_ALAW, 8) => Some(Self::ALaw),
+            (wav::WAVE_FORMAT_MULAW, 8) => Some(Self::MuLaw),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::U8 => "u8",
+            Self::S16Le => "s16le",
+            Self::S16Be => "s16be",
+            Self::S24Le => "s24le",
+            Self::S24Be => "s24be",
+            Self::S32Le => "s32le",
+            Self::S32Be => "s32be",
+            Self::F32Le => "f32le",
+            Self::F32Be => "f32be",
+            Self::F64Le => "f64le",
+            Self::F64Be => "f64be",
+            Self::MuLaw => "mulaw",
+            Self::ALaw => "alaw",
+        }
+    }
+
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            Self::U8 | Self::MuLaw | Self::ALaw => 1,
+            Self::S16Le | Self::S16Be => 2,
+            Self::S24Le | Self::S24Be => 3,
+            Self::S32Le | Self::S32Be | Self::F32Le | Self::F32Be => 4,
+            Self::F64Le | Self::F64Be => 8,
+        }
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        (self.bytes_per_sample() * 8) as u8
+    }
+
+    fn decode_sample(&self, bytes: &[u8]) -> f32 {
+        match self {
+            Self::U8 => (bytes[0] as f32 - 128.0) / 128.0,
+            Self::S16Le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32,
+            Self::S16Be => i16::from_be_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32,
+            Self::S24Le => {
+                (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 / 8388607.0
+            }
+            Self::S24Be => {
+                (i32::from_be_bytes([bytes[0], bytes[1], bytes[2], 0]) >> 8) as f32 / 8388607.0
+            }
+            Self::S32Le => {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32
+                    / i32::MAX as f32
+            }
+            Self::S32Be => {
+                i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32
+                    / i32::MAX as f32
+            }
+            Self::F32Le => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            Self::F32Be => f32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            Self::F64Le => f64::from_le_bytes(bytes[..8].try_into().unwrap()) as f32,
+            Self::F64Be => f64::from_be_bytes(bytes[..8].try_into().unwrap()) as f32,
+            Self::MuLaw => g711::mulaw_to_linear(bytes[0]) as f32 / 32768.0,
+            Self::ALaw => g711::alaw_to_linear(bytes[0]) as f32 / 32768.0,
+        }
+    }
+
+    fn encode_sample(&self, sample: f32, out: &mut Vec<u8>) {
+        match self {
+            Self::U8 => out.push((sample * 127.0 + 128.0).clamp(0.0, 255.0) as u8),
+            Self::S16Le => {
+                out.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes())
+            }
+            Self::S16Be => {
+                out.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_be_bytes())
+            }
+            Self::S24Le => {
+                out.extend_from_slice(&((sample * 8388607.0) as i32).to_le_bytes()[0..3])
+            }
+            Self::S24Be => {
+                out.extend_from_slice(&((sample * 8388607.0) as i32).to_be_bytes()[1..4])
+            }
+            Self::S32Le => {
+                out.extend_from_slice(&((sample * i32::MAX as f32) as i32).to_le_bytes())
+            }
+            Self::S32Be => {
+                out.extend_from_slice(&((sample * i32::MAX as f32) as i32).to_be_bytes())
+            }
+            Self::F32Le => out.extend_from_slice(&sample.to_le_bytes()),
+            Self::F32Be => out.extend_from_slice(&sample.to_be_bytes()),
+            Self::F64Le => out.extend_from_slice(&(sample as f64).to_le_bytes()),
+            Self::F64Be => out.extend_from_slice(&(sample as f64).to_be_bytes()),
+            Self::MuLaw => out.push(g711::linear_to_mulaw((sample * i16::MAX as f32) as i16)),
+            Self::ALaw => out.push(g711::linear_to_alaw((sample * i16::MAX as f32) as i16)),
+        }
+    }
+}
+
+impl std::fmt::Display for SampleEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for SampleEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = match s.trim().to_ascii_lowercase().as_str() {
+            "ulaw" | "pcmu" | "g711u" => "mulaw".to_string(),
+            "pcma" | "g711a" => "alaw".to_string(),
+            other => other.to_string(),
+        };
+        Self::ALL
+            .into_iter()
+            .find(|encoding| encoding.name() == normalized)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|e| e.name()).collect();
+                format!(
+                    "Unsupported sample encoding: {s} (expected one of: {})",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+pub fn convert_audio_bytes_to_samples(
+    audio_bytes: &[u8],
+    encoding: SampleEncoding,
+) -> Result<Vec<f32>, String> {
+    debug!(
+        "Converting {} bytes of {} audio to samples",
+        audio_bytes.len(),
+        encoding
+    );
+
+    let width = encoding.bytes_per_sample();
+    if !audio_bytes.len().is_multiple_of(width) {
+        error!(
+            "Invalid {} audio data: byte count ({}) not divisible by {}",
+            encoding,
+            audio_bytes.len(),
+            width
+        );
+        return Err(format!(
+            "Invalid {encoding} audio data: byte count not divisible by {width}"
+        ));
+    }
+
+    let samples: Vec<f32> = audio_bytes
+        .chunks_exact(width)
+        .map(|chunk| encoding.decode_sample(chunk))
+        .collect();
+    debug!("Converted {} audio to {} samples", encoding, samples.len());
+    Ok(samples)
+}
+
+pub fn convert_samples_to_audio_bytes(samples: &[f32], encoding: SampleEncoding) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(samples.len() * encoding.bytes_per_sample());
+    for &sample in samples {
+        encoding.encode_sample(sample, &mut bytes);
+    }
+    bytes
+}
+
+pub fn record_audio(config: &ClientConfig) -> Result<Vec<u8>> {
+    println!("🎤 Starting audio recording...");
+    println!("   Duration: {} seconds", config.record_duration);
+    println!("   Sample rate: {}Hz", config.sample_rate);
+    println!("   Channels: {}", config.channels);
+    println!("   Encoding: {}", config.encoding);
+
+    let host = cpal::default_host();
+    let device = host
+        .default_input_device()
+        .ok_or_else(|| anyhow!("No input device available"))?;
+
+    println!("🎙️  Using input device: {}", device.name()?);
+
+    let mut supported_configs_range = device
+        .supported_input_configs()
+        .map_err(|e| anyhow!("Error querying input configs: {}", e))?;
+
+    let supported_config = supported_configs_range
+        .next()
+        .ok_or_else(|| anyhow!("No supported config"))?
+        .with_sample_rate(cpal::SampleRate(config.sample_rate));
+
+    let config_cpal = supported_config.into();
+
+    let recorded_samples = Arc::new(Mutex::new(Vec::new()));
+    let recorded_samples_clone = Arc::clone(&recorded_samples);
+
+    let stream = device.build_input_stream(
+        &config_cpal,
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            let mut samples = recorded_samples_clone.lock().unwrap();
+            samples.extend_from_slice(data);
+        },
+        |err| eprintln!("Error in audio stream: {err}"),
+        None,
+    )?;
+
+    stream.play()?;
+
+    println!("🔴 Recording starting in...");
+    for i in (1..=3).rev() {
+        print!("   {i}... ");
+        std::io::stdout().flush().unwrap();
+        std::thread::sleep(Duration::from_secs(1));
+    }
+    println!("🎙️  GO!");
+
+    for remaining in (1..=config.record_duration).rev() {
+        if remaining % 5 == 0 || remaining <= 3 {
+            println!("   {remaining} seconds remaining...");
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+
+    drop(stream);
+    println!("⏹️  Recording stopped");
+
+    let samples = recorded_samples.lock().unwrap();
+    println!("📊 Recorded {} samples", samples.len());
+
+    let audio_bytes = convert_samples_to_audio_bytes(&samples, config.encoding);
+
+    println!("💾 Converted to {} bytes", audio_bytes.len());
+    Ok(audio_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// MPEG-1 layer III, 128 kbit/s, 44.1kHz, no padding: 417-byte frames.
+    fn mp3_frames(count: usize) -> Vec<u8> {
+        let mut frame = vec![0; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        frame.repeat(count)
+    }
+
+    /// AAC-LC ADTS at 44.1kHz, mono, with 100-byte frames.
+    fn adts_frames(count: usize) -> Vec<u8> {
+        let len = 100usize;
+        let mut frame = vec![0; len];
+        frame[..7].copy_from_slice(&[
+            0xFF,
+            0xF1,
+            0x50,
+            0x40 | (len >> 11) as u8,
+            (len >> 3) as u8,
+            ((len & 0x07) << 5) as u8 | 0x1F,
+            0xFC,
+        ]);
+        frame.repeat(count)
+    }
+
+    #[test]
+    fn detects_mp3_and_adts_streams() {
+        assert_eq!(
+            AudioContainer::detect(&mp3_frames(3), None),
+            AudioContainer::Mp3
+        );
+        assert_eq!(
+            AudioContainer::detect(&adts_frames(3), None),
+            AudioContainer::Aac
+        );
+    }
+
+    #[test]
+    fn raw_pcm_starting_like_a_sync_word_stays_raw() {
+        // s16le samples 0xFBFF and 0xF1FF followed by ordinary audio
+        for first in [[0xFF, 0xFB, 0x90, 0x00], [0xFF, 0xF1, 0x50, 0x40]] {
+            let mut bytes = first.to_vec();
+            bytes.extend((0..2000).map(|i| (i * 7 % 251) as u8));
+            assert_eq!(AudioContainer::detect(&bytes, None), AudioContainer::RawPcm);
+        }
+    }
+
+    #[test]
+    fn filename_hint_applies_without_magic() {
+        let bytes = vec![0x12; 64];
+        assert_eq!(
+            AudioContainer::detect(&bytes, Some("call.MP3")),
+            AudioContainer::Mp3
+        );
+        assert_eq!(
+            AudioContainer::detect(&bytes, Some("call.wav")),
+            AudioContainer::RawPcm
+        );
+    }
+}
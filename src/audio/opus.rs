@@ -0,0 +1,147 @@
+use audiopus::coder::Decoder;
+use audiopus::packet::Packet;
+use audiopus::{Channels, MutSignals, SampleRate};
+use log::{debug, error, warn};
+use symphonia::core::codecs::CodecParameters;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatReader;
+
+use crate::audio::DecodedAudio;
+
+/// libopus can decode straight to 16kHz, which saves a resampling pass
+/// before whisper.
+const OUTPUT_SAMPLE_RATE: u32 = 16000;
+
+/// Longest Opus packet is 120ms.
+const MAX_FRAME_SAMPLES: usize = OUTPUT_SAMPLE_RATE as usize * 120 / 1000;
+
+/// Fields of the `OpusHead` identification header (RFC 7845, section 5.1)
+/// needed for decoding.
+struct OpusHead {
+    channels: usize,
+    pre_skip: usize,
+    output_gain: i16,
+    mapping_family: u8,
+}
+
+fn parse_opus_head(extra_data: &[u8]) -> Option<OpusHead> {
+    if extra_data.len() < 19 || &extra_data[0..8] != b"OpusHead" {
+        return None;
+    }
+    Some(OpusHead {
+        channels: extra_data[9] as usize,
+        pre_skip: u16::from_le_bytes([extra_data[10], extra_data[11]]) as usize,
+        output_gain: i16::from_le_bytes([extra_data[16], extra_data[17]]),
+        mapping_family: extra_data[18],
+    })
+}
+
+pub fn decode_opus_track(
+    reader: &mut dyn FormatReader,
+    track_id: u32,
+    codec_params: &CodecParameters,
+    extension: &str,
+) -> Result<DecodedAudio, String> {
+    let head = codec_params.extra_data.as_deref().and_then(parse_opus_head);
+    let channel_count = head
+        .as_ref()
+        .map(|h| h.channels)
+        .or_else(|| codec_params.channels.map(|c| c.count()))
+        .ok_or_else(|| {
+            format!("Could not determine channel count of Opus audio in {extension} file")
+        })?;
+
+    let channels = match channel_count {
+        1 => Channels::Mono,
+        2 => Channels::Stereo,
+        n => {
+            error!("Unsupported Opus channel count: {n}");
+            return Err(format!(
+                "Unsupported Opus channel count: {n} (only mono and stereo are supported)"
+            ));
+        }
+    };
+
+    if let Some(family) = head.as_ref().map(|h| h.mapping_family).filter(|&f| f != 0) {
+        warn!("Opus channel mapping family {family} with {channel_count} channels, decoding as a single stream");
+    }
+
+    let mut decoder = Decoder::new(SampleRate::Hz16000, channels).map_err(|e| {
+        error!("Failed to create Opus decoder: {e}");
+        format!("Failed to create Opus decoder: {e}")
+    })?;
+
+    if let Some(gain) = head.as_ref().map(|h| h.output_gain).filter(|&g| g != 0) {
+        decoder
+            .set_gain(gain as i32)
+            .map_err(|e| format!("Failed to apply Opus output gain: {e}"))?;
+    }
+
+    // Pre-skip is expressed at 48kHz
+    let pre_skip = head
+        .as_ref()
+        .map(|h| h.pre_skip)
+        .or(codec_params.delay.map(|d| d as usize))
+        .unwrap_or(0)
+        * OUTPUT_SAMPLE_RATE as usize
+        / 48000;
+
+    debug!(
+        "Decoding Opus track from {extension}: {channel_count} channels, pre-skip {pre_skip} samples at {OUTPUT_SAMPLE_RATE}Hz"
+    );
+
+    let mut frame = vec![0.0f32; MAX_FRAME_SAMPLES * channel_count];
+    let mut samples = Vec::new();
+
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => {
+                error!("Failed to read {extension} packet: {e}");
+                return Err(format!("Failed to read {extension} audio: {e}"));
+            }
+        };
+
+        if packet.track_id() != track_id || packet.data.is_empty() {
+            continue;
+        }
+
+        // Bad packets are skipped, as in `codec::decode_track`
+        let input = match Packet::try_from(packet.data.as_ref()) {
+            Ok(input) => input,
+            Err(e) => {
+                warn!("Skipping invalid Opus packet: {e}");
+                continue;
+            }
+        };
+        let output = MutSignals::try_from(frame.as_mut_slice())
+            .map_err(|e| format!("Invalid Opus output buffer: {e}"))?;
+
+        match decoder.decode_float(Some(input), output, false) {
+            Ok(decoded_frames) => {
+                samples.extend_from_slice(&frame[..decoded_frames * channel_count]);
+            }
+            Err(e) => warn!("Skipping undecodable Opus packet: {e}"),
+        }
+    }
+
+    let skip = (pre_skip * channel_count).min(samples.len());
+    samples.drain(..skip);
+
+    debug!(
+        "Decoded Opus audio: {} samples, {}Hz, {} channels",
+        samples.len(),
+        OUTPUT_SAMPLE_RATE,
+        channel_count
+    );
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: OUTPUT_SAMPLE_RATE,
+        channels: channel_count,
+        format: format!("{extension}/opus"),
+    })
+}
@@ -0,0 +1,310 @@
+use log::{debug, warn};
+
+pub const WAVE_FORMAT_PCM: u16 = 0x0001;
+pub const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+pub const WAVE_FORMAT_ALAW: u16 = 0x0006;
+pub const WAVE_FORMAT_MULAW: u16 = 0x0007;
+pub const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+#[derive(Clone, Debug)]
+pub struct WavFormat {
+    pub format_tag: u16,
+    pub channels: usize,
+    pub sample_rate: u32,
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+    pub valid_bits_per_sample: u16,
+}
+
+impl WavFormat {
+    /// Width of a single sample as stored in the data chunk. This can be
+    /// larger than `valid_bits_per_sample`, e.g. 24-bit audio in 32-bit slots.
+    pub fn container_bits(&self) -> u16 {
+        if self.channels > 0 && self.block_align > 0 {
+            (self.block_align as usize / self.channels * 8) as u16
+        } else {
+            self.bits_per_sample
+        }
+    }
+}
+
+pub struct WavAudio<'a> {
+    pub format: WavFormat,
+    pub data: &'a [u8],
+}
+
+pub fn is_wav(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WAVE"
+}
+
+pub fn parse_wav(bytes: &[u8]) -> Result<WavAudio<'_>, String> {
+    if !is_wav(bytes) {
+        return Err("Not a RIFF/WAVE file".to_string());
+    }
+
+    let mut format: Option<WavFormat> = None;
+    let mut data: Option<&[u8]> = None;
+    let mut offset = 12;
+
+    while offset + 8 <= bytes.len() {
+        let chunk_id = &bytes[offset..offset + 4];
+        let declared_size = read_u32(bytes, offset + 4) as usize;
+        let body_start = offset + 8;
+        let available = bytes.len() - body_start;
+
+        // Streaming writers leave the data size as 0 or 0xFFFFFFFF, and
+        // truncated uploads declare more than they carry.
+        let chunk_size = if declared_size > available {
+            if chunk_id == b"data" {
+                warn!(
+                    "WAV data chunk declares {declared_size} bytes but only {available} are present, using available data"
+                );
+                available
+            } else if format.is_some() && data.is_some() {
+                // Trailing metadata (LIST, id3) cut short at the end of the
+                // file, everything needed for decoding is already parsed
+                warn!(
+                    "Truncated WAV chunk {:?} after the data chunk: declares {} bytes, {} available, ignoring the rest of the file",
+                    String::from_utf8_lossy(chunk_id),
+                    declared_size,
+                    available
+                );
+                break;
+            } else {
+                return Err(format!(
+                    "Truncated WAV chunk {:?}: declares {} bytes, {} available",
+                    String::from_utf8_lossy(chunk_id),
+                    declared_size,
+                    available
+                ));
+            }
+        } else if declared_size == 0 && chunk_id == b"data" {
+            available
+        } else {
+            declared_size
+        };
+
+        let body = &bytes[body_start..body_start + chunk_size];
+        debug!(
+            "WAV chunk {:?} at offset {}: {} bytes",
+            String::from_utf8_lossy(chunk_id),
+            offset,
+            chunk_size
+        );
+
+        match chunk_id {
+            b"fmt " => format = Some(parse_fmt_chunk(body)?),
+            b"data" if data.is_none() => data = Some(body),
+            // LIST, fact, bext, cue, JUNK, PAD and anything else carries no
+            // sample data
+            _ => {}
+        }
+
+        // Chunks are word aligned, odd sizes are followed by a pad byte
+        offset = body_start + chunk_size + (chunk_size & 1);
+    }
+
+    let format = format.ok_or_else(|| "WAV file has no fmt chunk".to_string())?;
+    let data = data.ok_or_else(|| "WAV file has no data chunk".to_string())?;
+
+    if format.channels == 0 {
+        return Err("WAV file declares zero channels".to_string());
+    }
+    if format.sample_rate == 0 {
+        return Err("WAV file declares a zero sample rate".to_string());
+    }
+
+    // Drop a trailing partial frame rather than rejecting the whole upload
+    let frame_bytes = format.block_align as usize;
+    let data = if frame_bytes > 0 && !data.len().is_multiple_of(frame_bytes) {
+        let usable = data.len() - data.len() % frame_bytes;
+        warn!(
+            "WAV data length {} is not a multiple of block align {}, truncating to {} bytes",
+            data.len(),
+            frame_bytes,
+            usable
+        );
+        &data[..usable]
+    } else {
+        data
+    };
+
+    debug!(
+        "Parsed WAV header: format_tag=0x{:04X}, {}Hz, {} channels, {} bits ({} valid), {} data bytes",
+        format.format_tag,
+        format.sample_rate,
+        format.channels,
+        format.bits_per_sample,
+        format.valid_bits_per_sample,
+        data.len()
+    );
+
+    Ok(WavAudio { format, data })
+}
+
+fn parse_fmt_chunk(body: &[u8]) -> Result<WavFormat, String> {
+    if body.len() < 16 {
+        return Err(format!("WAV fmt chunk too short: {} bytes", body.len()));
+    }
+
+    let mut format_tag = read_u16(body, 0);
+    let channels = read_u16(body, 2) as usize;
+    let sample_rate = read_u32(body, 4);
+    let block_align = read_u16(body, 12);
+    let bits_per_sample = read_u16(body, 14);
+    let mut valid_bits_per_sample = bits_per_sample;
+
+    if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        // cbSize(2) validBits(2) channelMask(4) subFormat GUID(16)
+        if body.len() < 40 {
+            return Err(format!(
+                "WAVE_FORMAT_EXTENSIBLE fmt chunk too short: {} bytes",
+                body.len()
+            ));
+        }
+        let valid_bits = read_u16(body, 18);
+        if valid_bits > 0 {
+            valid_bits_per_sample = valid_bits;
+        }
+        // The first two bytes of the sub-format GUID hold the actual format tag
+        format_tag = read_u16(body, 24);
+        debug!(
+            "WAVE_FORMAT_EXTENSIBLE: sub-format 0x{:04X}, channel mask 0x{:08X}",
+            format_tag,
+            read_u32(body, 20)
+        );
+    }
+
+    Ok(WavFormat {
+        format_tag,
+        channels,
+        sample_rate,
+        block_align,
+        bits_per_sample,
+        valid_bits_per_sample,
+    })
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn pcm_fmt(channels: u16, sample_rate: u32, bits: u16) -> Vec<u8> {
+        let block_align = channels * bits / 8;
+        let mut body = Vec::new();
+        body.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+        body.extend_from_slice(&channels.to_le_bytes());
+        body.extend_from_slice(&sample_rate.to_le_bytes());
+        body.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+        body.extend_from_slice(&block_align.to_le_bytes());
+        body.extend_from_slice(&bits.to_le_bytes());
+        body
+    }
+
+    fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn parses_plain_pcm() {
+        let bytes = riff(&[
+            chunk(b"fmt ", &pcm_fmt(2, 44100, 16)),
+            chunk(b"data", &[1, 0, 2, 0, 3, 0, 4, 0]),
+        ]);
+        let wav = parse_wav(&bytes).unwrap();
+        assert_eq!(wav.format.format_tag, WAVE_FORMAT_PCM);
+        assert_eq!(wav.format.channels, 2);
+        assert_eq!(wav.format.sample_rate, 44100);
+        assert_eq!(wav.format.container_bits(), 16);
+        assert_eq!(wav.data, &[1, 0, 2, 0, 3, 0, 4, 0]);
+    }
+
+    #[test]
+    fn skips_odd_sized_list_chunk_with_padding() {
+        let bytes = riff(&[
+            chunk(b"fmt ", &pcm_fmt(1, 16000, 16)),
+            chunk(b"LIST", b"INFOabc"),
+            chunk(b"data", &[5, 0, 6, 0]),
+        ]);
+        let wav = parse_wav(&bytes).unwrap();
+        assert_eq!(wav.data, &[5, 0, 6, 0]);
+    }
+
+    #[test]
+    fn resolves_extensible_sub_format() {
+        let mut body = pcm_fmt(2, 48000, 32);
+        body[0..2].copy_from_slice(&WAVE_FORMAT_EXTENSIBLE.to_le_bytes());
+        body.extend_from_slice(&22u16.to_le_bytes()); // cbSize
+        body.extend_from_slice(&24u16.to_le_bytes()); // valid bits
+        body.extend_from_slice(&0x3u32.to_le_bytes()); // channel mask
+        body.extend_from_slice(&WAVE_FORMAT_IEEE_FLOAT.to_le_bytes());
+        body.extend_from_slice(&[0; 14]);
+        let bytes = riff(&[chunk(b"fmt ", &body), chunk(b"data", &[0; 8])]);
+
+        let wav = parse_wav(&bytes).unwrap();
+        assert_eq!(wav.format.format_tag, WAVE_FORMAT_IEEE_FLOAT);
+        assert_eq!(wav.format.bits_per_sample, 32);
+        assert_eq!(wav.format.valid_bits_per_sample, 24);
+        assert_eq!(wav.format.container_bits(), 32);
+    }
+
+    #[test]
+    fn uses_available_bytes_of_truncated_data_chunk() {
+        let mut bytes = riff(&[chunk(b"fmt ", &pcm_fmt(1, 16000, 16))]);
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0, 2, 0, 3]);
+        let wav = parse_wav(&bytes).unwrap();
+        assert_eq!(wav.data, &[1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn ignores_truncated_trailing_chunk() {
+        let mut bytes = riff(&[
+            chunk(b"fmt ", &pcm_fmt(1, 16000, 16)),
+            chunk(b"data", &[1, 0, 2, 0]),
+        ]);
+        bytes.extend_from_slice(b"id3 ");
+        bytes.extend_from_slice(&100u32.to_le_bytes());
+        bytes.extend_from_slice(b"ID3");
+        let wav = parse_wav(&bytes).unwrap();
+        assert_eq!(wav.data, &[1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn rejects_truncated_chunk_before_data() {
+        let mut bytes = riff(&[chunk(b"fmt ", &pcm_fmt(1, 16000, 16))]);
+        bytes.extend_from_slice(b"LIST");
+        bytes.extend_from_slice(&100u32.to_le_bytes());
+        bytes.extend_from_slice(b"INFO");
+        assert!(parse_wav(&bytes).is_err());
+    }
+}
@@ -1,11 +1,14 @@
 use clap::{Parser, Subcommand};
 
+use crate::audio::SampleEncoding;
+use crate::whisper::transcriber::Task;
+
 #[derive(Parser)]
 #[command(
     name = "open-transcribe",
     about = "Open Transcribe - Audio Recording & Transcription",
     long_about = "A unified tool for transcribing audio files or recording and transcribing audio in real-time, with built-in server functionality.",
-    after_help = "EXAMPLES:\n    # Start the transcription server\n    open-transcribe serve\n\n    # Download a whisper model\n    open-transcribe download tiny\n\n    # Download a model to specific directory\n    open-transcribe download base ./models\n\n    # Transcribe an existing audio file\n    open-transcribe file my_audio.wav\n\n    # Record 10 seconds of audio and transcribe\n    open-transcribe record --duration 10\n\n    # Record with custom audio settings\n    open-transcribe record --duration 15 --sample-rate 44100 --channels 2 --bit-depth 24\n\n    # Use a different server when in client mode\n    open-transcribe file audio.wav --server-url http://my-server:8080"
+    after_help = "EXAMPLES:\n    # Start the transcription server\n    open-transcribe serve\n\n    # Download a whisper model\n    open-transcribe download tiny\n\n    # Download a model to specific directory\n    open-transcribe download base ./models\n\n    # Transcribe an existing audio file\n    open-transcribe file my_audio.wav\n\n    # Record 10 seconds of audio and transcribe\n    open-transcribe record --duration 10\n\n    # Record with custom audio settings\n    open-transcribe record --duration 15 --sample-rate 44100 --channels 2 --bit-depth 24\n\n    # Transcribe raw 32-bit float PCM\n    open-transcribe file audio.f32 --sample-rate 48000 --encoding f32le\n\n    # Transcribe an 8kHz G.711 mu-law call recording\n    open-transcribe file call.ulaw --sample-rate 8000 --encoding mulaw\n\n    # Translate a Spanish recording into English text\n    open-transcribe file entrevista.mp3 --task translate\n\n    # Transcribe with a specific model loaded by the server\n    open-transcribe file interview.wav --model large-v3\n\n    # Use a different server when in client mode\n    open-transcribe file audio.wav --server-url http://my-server:8080"
 )]
 pub struct Cli {
     #[command(subcommand)]
@@ -45,6 +48,27 @@ pub enum Commands {
 
         #[arg(long, default_value = "16", value_parser = validate_bit_depth)]
         bit_depth: u8,
+
+        #[arg(
+            long,
+            value_parser = validate_encoding,
+            help = "Sample encoding (u8, s16le, s16be, s24le, s24be, s32le, s32be, f32le, f32be, f64le, f64be, mulaw, alaw), overrides --bit-depth"
+        )]
+        encoding: Option<SampleEncoding>,
+
+        #[arg(
+            long,
+            default_value = "transcribe",
+            value_parser = validate_task,
+            help = "transcribe in the spoken language, or translate to English (needs a multilingual model)"
+        )]
+        task: Task,
+
+        #[arg(
+            long,
+            help = "Model to transcribe with, as listed by the server's /api/v1/models (defaults to the server's default model)"
+        )]
+        model: Option<String>,
     },
     #[command(name = "record")]
     Record {
@@ -62,13 +86,49 @@ pub enum Commands {
 
         #[arg(long, default_value = "16", value_parser = validate_bit_depth)]
         bit_depth: u8,
+
+        #[arg(
+            long,
+            value_parser = validate_encoding,
+            help = "Sample encoding (u8, s16le, s16be, s24le, s24be, s32le, s32be, f32le, f32be, f64le, f64be, mulaw, alaw), overrides --bit-depth"
+        )]
+        encoding: Option<SampleEncoding>,
+
+        #[arg(
+            long,
+            default_value = "transcribe",
+            value_parser = validate_task,
+            help = "transcribe in the spoken language, or translate to English (needs a multilingual model)"
+        )]
+        task: Task,
+
+        #[arg(
+            long,
+            help = "Model to transcribe with, as listed by the server's /api/v1/models (defaults to the server's default model)"
+        )]
+        model: Option<String>,
     },
 }
 
 pub fn validate_bit_depth(s: &str) -> Result<u8, String> {
     match s.parse::<u8>() {
-        Ok(16) | Ok(24) | Ok(32) => Ok(s.parse().unwrap()),
-        Ok(_) => Err("Bit depth must be 16, 24, or 32".to_string()),
+        Ok(8) | Ok(16) | Ok(24) | Ok(32) => Ok(s.parse().unwrap()),
+        Ok(_) => Err("Bit depth must be 8, 16, 24, or 32".to_string()),
         Err(_) => Err("Invalid bit depth value".to_string()),
     }
 }
+
+pub fn validate_encoding(s: &str) -> Result<SampleEncoding, String> {
+    s.parse()
+}
+
+pub fn validate_task(s: &str) -> Result<Task, String> {
+    s.parse()
+}
+
+/// An explicit `--encoding` wins over the encoding implied by `--bit-depth`.
+pub fn resolve_encoding(bit_depth: u8, encoding: Option<SampleEncoding>) -> SampleEncoding {
+    encoding
+        .or_else(|| SampleEncoding::from_bit_depth(bit_depth))
+        .unwrap_or(SampleEncoding::S16Le)
+}
@@ -33,27 +33,36 @@ pub async fn send_transcription_request(config: &ClientConfig) -> Result<Value>
     );
 
     let filename = if config.record_mode {
-        "recording.wav".to_string()
+        "recording.pcm".to_string()
     } else {
         config.audio_file.clone().unwrap()
     };
 
-    let form = reqwest::multipart::Form::new()
+    let mut form = reqwest::multipart::Form::new()
         .part(
             "audio",
             reqwest::multipart::Part::bytes(audio_data).file_name(filename),
         )
         .text("sample_rate", config.sample_rate.to_string())
         .text("channels", config.channels.to_string())
-        .text("bit_depth", config.bit_depth.to_string());
+        .text("bit_depth", config.encoding.bit_depth().to_string())
+        .text("encoding", config.encoding.to_string())
+        .text("task", config.task.to_string());
+    if let Some(model) = &config.model {
+        form = form.text("model", model.clone());
+    }
 
     println!(
         "🚀 Sending transcription request to: {}/api/v1/transcribe",
         config.server_url
     );
     println!(
-        "   Sample rate: {}Hz, Channels: {}, Bit depth: {}",
-        config.sample_rate, config.channels, config.bit_depth
+        "   Sample rate: {}Hz, Channels: {}, Encoding: {}, Task: {}, Model: {}",
+        config.sample_rate,
+        config.channels,
+        config.encoding,
+        config.task,
+        config.model.as_deref().unwrap_or("server default")
     );
 
     let response = client
@@ -110,8 +119,8 @@ pub async fn run_client(config: ClientConfig) -> Result<()> {
         println!("🎤 Recording Mode");
         println!("   Duration: {} seconds", config.record_duration);
         println!(
-            "   Audio format: {}Hz, {} channels, {}-bit",
-            config.sample_rate, config.channels, config.bit_depth
+            "   Audio format: {}Hz, {} channels, {}",
+            config.sample_rate, config.channels, config.encoding
         );
         println!("   Make sure your microphone is connected and working!");
         println!();
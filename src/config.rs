@@ -1,10 +1,16 @@
+use crate::audio::SampleEncoding;
+use crate::whisper::transcriber::Task;
+
 #[derive(Debug)]
 pub struct ClientConfig {
     pub server_url: String,
     pub audio_file: Option<String>,
     pub sample_rate: u32,
     pub channels: usize,
-    pub bit_depth: u8,
+    pub encoding: SampleEncoding,
+    pub task: Task,
+    /// Server-side model name, `None` for the server's default.
+    pub model: Option<String>,
     pub record_mode: bool,
     pub record_duration: u32,
 }
@@ -15,14 +21,18 @@ impl ClientConfig {
         audio_file: String,
         sample_rate: u32,
         channels: usize,
-        bit_depth: u8,
+        encoding: SampleEncoding,
+        task: Task,
+        model: Option<String>,
     ) -> Self {
         Self {
             server_url,
             audio_file: Some(audio_file),
             sample_rate,
             channels,
-            bit_depth,
+            encoding,
+            task,
+            model,
             record_mode: false,
             record_duration: 0,
         }
@@ -32,7 +42,9 @@ impl ClientConfig {
         server_url: String,
         sample_rate: u32,
         channels: usize,
-        bit_depth: u8,
+        encoding: SampleEncoding,
+        task: Task,
+        model: Option<String>,
         record_duration: u32,
     ) -> Self {
         Self {
@@ -40,7 +52,9 @@ impl ClientConfig {
             audio_file: None,
             sample_rate,
             channels,
-            bit_depth,
+            encoding,
+            task,
+            model,
             record_mode: true,
             record_duration,
         }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 
-use open_transcribe::cli::{Cli, Commands};
+use open_transcribe::cli::{resolve_encoding, Cli, Commands};
 use open_transcribe::client::run_client;
 use open_transcribe::config::ClientConfig;
 use open_transcribe::download::download_model;
@@ -32,13 +32,18 @@ async fn main() -> Result<()> {
             sample_rate,
             channels,
             bit_depth,
+            encoding,
+            task,
+            model,
         } => {
             let config = ClientConfig::new_file_mode(
                 server_url,
                 audio_file,
                 sample_rate,
                 channels,
-                bit_depth,
+                resolve_encoding(bit_depth, encoding),
+                task,
+                model,
             );
             run_client(config).await?;
         }
@@ -48,12 +53,17 @@ async fn main() -> Result<()> {
             sample_rate,
             channels,
             bit_depth,
+            encoding,
+            task,
+            model,
         } => {
             let config = ClientConfig::new_record_mode(
                 server_url,
                 sample_rate,
                 channels,
-                bit_depth,
+                resolve_encoding(bit_depth, encoding),
+                task,
+                model,
                 duration,
             );
             run_client(config).await?;
@@ -5,14 +5,43 @@ use anyhow::Result;
 use futures_util::TryStreamExt;
 use log::{debug, error, info, warn};
 
-use crate::audio::convert_audio_bytes_to_samples;
-use crate::whisper::config::WhisperConfig;
-use crate::whisper::transcriber::{InputAudio, SimpleTranscriber};
+use crate::audio::analysis::AudioInfo;
+use crate::audio::{decode_audio, RawPcmFormat, SampleEncoding};
+use crate::whisper::config::{parse_list, WhisperConfig};
+use crate::whisper::decoding::{DecodingStrategy, MAX_DECODERS};
+use crate::whisper::downmix::{validate_layout, DownmixStrategy};
+use crate::whisper::queue::SubmitError;
+use crate::whisper::registry::{LoadedModel, ModelInfo, ModelRegistry};
+use crate::whisper::transcriber::{is_supported_language, ChannelMode, Task, TranscribeOptions};
+
+/// Seconds a client is asked to wait before retrying when the queue is full.
+const RETRY_AFTER_SECONDS: u32 = 5;
 
 #[derive(serde::Serialize)]
 pub struct TranscriptionDto {
     pub text: String,
     pub segments: Option<Vec<TranscriptionSegment>>,
+    /// Name of the model that produced the transcript.
+    pub model: String,
+    pub channel_mode: ChannelMode,
+    pub task: Task,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<DetectedLanguage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_info: Option<AudioInfo>,
+}
+
+#[derive(serde::Serialize)]
+pub struct DetectedLanguage {
+    pub code: String,
+    pub probability: f32,
+    pub candidates: Vec<LanguageProbability>,
+}
+
+#[derive(serde::Serialize)]
+pub struct LanguageProbability {
+    pub language: String,
+    pub probability: f32,
 }
 
 #[derive(serde::Serialize)]
@@ -21,10 +50,43 @@ pub struct TranscriptionSegment {
     pub end: usize,
     pub text: String,
     pub confidence: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<TranscriptionWord>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<Vec<TranscriptionToken>>,
+}
+
+#[derive(serde::Serialize)]
+pub struct TranscriptionWord {
+    pub word: String,
+    pub start: usize,
+    pub end: usize,
+    pub probability: f32,
+}
+
+#[derive(serde::Serialize)]
+pub struct TranscriptionToken {
+    pub text: String,
+    pub id: i32,
+    pub p: f32,
+    pub plog: f32,
+    pub t0: usize,
+    pub t1: usize,
+    pub special: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct ModelsDto<'a> {
+    pub default: &'a str,
+    pub models: Vec<&'a ModelInfo>,
 }
 
 pub struct AppState {
-    pub transcriber: SimpleTranscriber,
+    pub registry: ModelRegistry,
 }
 
 #[get("/api/v1/health")]
@@ -36,6 +98,15 @@ pub async fn health_check() -> impl Responder {
     }))
 }
 
+#[get("/api/v1/models")]
+pub async fn list_models(data: web::Data<AppState>) -> impl Responder {
+    debug!("Models endpoint called");
+    HttpResponse::Ok().json(ModelsDto {
+        default: data.registry.default_model(),
+        models: data.registry.models(),
+    })
+}
+
 #[post("/api/v1/transcribe")]
 pub async fn transcribe_upload(
     data: web::Data<AppState>,
@@ -44,53 +115,354 @@ pub async fn transcribe_upload(
     debug!("Transcription request received");
 
     let mut audio_data: Option<Vec<u8>> = None;
-    let mut sample_rate: u32 = 16000; // 16kHz
-    let mut channels: usize = 1;
-    let mut bit_depth: u8 = 16;
+    let mut audio_filename: Option<String> = None;
+    // Only applied to headerless PCM, containers carry their own format
+    let mut raw_format = RawPcmFormat::default();
+    let mut bit_depth: Option<u8> = None;
+    let mut encoding: Option<String> = None;
+    let mut model: Option<String> = None;
+    let mut options = TranscribeOptions::default();
 
     // Process multipart fields
     while let Some(field) = payload.try_next().await.unwrap_or(None) {
         match field.name() {
-            Some("audio") => match read_field_data(field).await {
-                Ok(data) => {
-                    debug!("Audio data received: {} bytes", data.len());
-                    audio_data = Some(data);
-                }
-                Err(e) => {
-                    error!("Failed to read audio data: {e}");
-                    return HttpResponse::BadRequest().json(serde_json::json!({
-                        "error": "Failed to read audio data"
-                    }));
-                }
-            },
-            Some("sample_rate") => {
-                if let Ok(field_data) = read_field_data(field).await {
-                    if let Ok(text) = String::from_utf8(field_data) {
-                        sample_rate = text.trim().parse().unwrap_or(16000);
-                        debug!("Sample rate set to: {sample_rate}");
+            Some("audio") => {
+                audio_filename = field
+                    .content_disposition()
+                    .and_then(|cd| cd.get_filename())
+                    .map(str::to_string);
+                match read_field_data(field).await {
+                    Ok(data) => {
+                        debug!("Audio data received: {} bytes", data.len());
+                        audio_data = Some(data);
                     }
+                    Err(e) => {
+                        error!("Failed to read audio data: {e}");
+                        return HttpResponse::BadRequest().json(serde_json::json!({
+                            "error": "Failed to read audio data"
+                        }));
+                    }
+                }
+            }
+            Some("sample_rate") => {
+                if let Some(text) = read_text_field(field).await {
+                    raw_format.sample_rate = text.trim().parse().unwrap_or(16000);
+                    debug!("Sample rate set to: {}", raw_format.sample_rate);
                 }
             }
             Some("channels") => {
-                if let Ok(field_data) = read_field_data(field).await {
-                    if let Ok(text) = String::from_utf8(field_data) {
-                        channels = text.trim().parse().unwrap_or(1);
-                        debug!("Channels set to: {channels}");
-                    }
+                if let Some(text) = read_text_field(field).await {
+                    raw_format.channels = text.trim().parse().unwrap_or(1);
+                    debug!("Channels set to: {}", raw_format.channels);
                 }
             }
             Some("bit_depth") => {
-                if let Ok(field_data) = read_field_data(field).await {
-                    if let Ok(text) = String::from_utf8(field_data) {
-                        bit_depth = text.trim().parse().unwrap_or(16);
-                        debug!("Bit depth set to: {bit_depth}");
+                if let Some(text) = read_text_field(field).await {
+                    bit_depth = Some(text.trim().parse().unwrap_or(16));
+                    debug!("Bit depth set to: {bit_depth:?}");
+                }
+            }
+            Some("encoding") => {
+                if let Some(text) = read_text_field(field).await {
+                    debug!("Encoding set to: {}", text.trim());
+                    encoding = Some(text);
+                }
+            }
+            Some("language") => {
+                if let Some(text) = read_text_field(field).await {
+                    let language = text.trim().to_ascii_lowercase();
+                    if !is_supported_language(&language) {
+                        warn!("Unsupported language in transcription request: {language}");
+                        return HttpResponse::BadRequest().json(serde_json::json!({
+                            "error": format!("Unsupported language: {language}")
+                        }));
+                    }
+                    debug!("Language set to: {language}");
+                    options.language = Some(language);
+                }
+            }
+            Some("model") => {
+                if let Some(text) = read_text_field(field).await {
+                    let name = text.trim();
+                    if !data.registry.contains(name) {
+                        warn!("Unknown model in transcription request: {name}");
+                        return HttpResponse::BadRequest().json(serde_json::json!({
+                            "error": format!(
+                                "Unknown model: {name} (available: {})",
+                                data.registry.names().join(", ")
+                            )
+                        }));
                     }
+                    debug!("Model set to: {name}");
+                    model = Some(name.to_string());
+                }
+            }
+            Some("task") => {
+                if let Some(text) = read_text_field(field).await {
+                    match text.parse() {
+                        Ok(task) => {
+                            debug!("Task set to: {task}");
+                            options.task = task;
+                        }
+                        Err(error_msg) => {
+                            warn!("Invalid task in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some("initial_prompt") => {
+                if let Some(text) = read_text_field(field).await {
+                    debug!("Initial prompt set to: {:?}", text.trim());
+                    options.initial_prompt = Some(text);
+                }
+            }
+            Some("vocabulary") => {
+                if let Some(text) = read_text_field(field).await {
+                    options.vocabulary = parse_list(&text);
+                    debug!("Vocabulary hints set to: {:?}", options.vocabulary);
+                }
+            }
+            Some("decoding_strategy") => {
+                if let Some(text) = read_text_field(field).await {
+                    match text.parse() {
+                        Ok(strategy) => {
+                            debug!("Decoding strategy set to: {strategy}");
+                            options.decoding_strategy = Some(strategy);
+                        }
+                        Err(error_msg) => {
+                            warn!(
+                                "Invalid decoding strategy in transcription request: {error_msg}"
+                            );
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some(
+                name @ ("beam_size" | "best_of" | "temperature" | "temperature_inc"
+                | "entropy_threshold" | "logprob_threshold"),
+            ) => {
+                let name = name.to_string();
+                if let Some(text) = read_text_field(field).await {
+                    let result = match name.as_str() {
+                        "beam_size" => {
+                            parse_count(&name, &text).map(|v| options.beam_size = Some(v))
+                        }
+                        "best_of" => parse_count(&name, &text).map(|v| options.best_of = Some(v)),
+                        "temperature" => {
+                            parse_temperature(&name, &text).map(|v| options.temperature = Some(v))
+                        }
+                        "temperature_inc" => parse_temperature(&name, &text)
+                            .map(|v| options.temperature_inc = Some(v)),
+                        "entropy_threshold" => parse_threshold(&name, &text)
+                            .map(|v| options.entropy_threshold = Some(v)),
+                        _ => parse_threshold(&name, &text)
+                            .map(|v| options.logprob_threshold = Some(v)),
+                    };
+                    match result {
+                        Ok(()) => debug!("Decoding {name} set to: {}", text.trim()),
+                        Err(error_msg) => {
+                            warn!("Invalid {name} in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some("downmix") => {
+                if let Some(text) = read_text_field(field).await {
+                    match text.parse() {
+                        Ok(strategy) => {
+                            debug!("Downmix strategy set to: {strategy}");
+                            options.downmix = Some(strategy);
+                        }
+                        Err(error_msg) => {
+                            warn!("Invalid downmix strategy in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some("vad") => {
+                if let Some(text) = read_text_field(field).await {
+                    match parse_flag(&text) {
+                        Ok(enabled) => {
+                            debug!("VAD set to: {enabled}");
+                            options.vad = Some(enabled);
+                        }
+                        Err(error_msg) => {
+                            warn!("Invalid vad flag in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some(name @ ("word_timestamps" | "verbose")) => {
+                let name = name.to_string();
+                if let Some(text) = read_text_field(field).await {
+                    match parse_flag(&text) {
+                        Ok(enabled) => {
+                            debug!("Output option {name} set to: {enabled}");
+                            match name.as_str() {
+                                "word_timestamps" => options.word_timestamps = enabled,
+                                _ => options.verbose = enabled,
+                            }
+                        }
+                        Err(error_msg) => {
+                            warn!("Invalid {name} flag in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some(name @ ("dc_removal" | "highpass" | "limiter" | "denoise")) => {
+                let name = name.to_string();
+                if let Some(text) = read_text_field(field).await {
+                    match parse_flag(&text) {
+                        Ok(enabled) => {
+                            debug!("Preprocessing {name} set to: {enabled}");
+                            match name.as_str() {
+                                "dc_removal" => options.dc_removal = Some(enabled),
+                                "highpass" => options.highpass = Some(enabled),
+                                "limiter" => options.limiter = Some(enabled),
+                                _ => options.denoise = Some(enabled),
+                            }
+                        }
+                        Err(error_msg) => {
+                            warn!("Invalid {name} flag in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some("normalize") => {
+                if let Some(text) = read_text_field(field).await {
+                    match text.parse() {
+                        Ok(mode) => {
+                            debug!("Normalization set to: {mode}");
+                            options.normalize = Some(mode);
+                        }
+                        Err(error_msg) => {
+                            warn!("Invalid normalization in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some("denoise_strength") => {
+                if let Some(text) = read_text_field(field).await {
+                    match text.trim().parse::<f32>() {
+                        Ok(strength) if strength.is_finite() && strength >= 0.0 => {
+                            debug!("Noise reduction strength set to: {strength}");
+                            options.denoise_strength = Some(strength);
+                        }
+                        _ => {
+                            let error_msg = format!("Invalid denoise strength: {}", text.trim());
+                            warn!("Invalid denoise strength in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some("resample_quality") => {
+                if let Some(text) = read_text_field(field).await {
+                    match text.parse() {
+                        Ok(quality) => {
+                            debug!("Resample quality set to: {quality}");
+                            options.resample_quality = Some(quality);
+                        }
+                        Err(error_msg) => {
+                            warn!("Invalid resample quality in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some("channel_mode") => {
+                if let Some(text) = read_text_field(field).await {
+                    match text.parse() {
+                        Ok(mode) => {
+                            debug!("Channel mode set to: {mode}");
+                            options.channel_mode = mode;
+                        }
+                        Err(error_msg) => {
+                            warn!("Invalid channel mode in transcription request: {error_msg}");
+                            return HttpResponse::BadRequest().json(serde_json::json!({
+                                "error": error_msg
+                            }));
+                        }
+                    }
+                }
+            }
+            Some("speaker_labels") => {
+                if let Some(text) = read_text_field(field).await {
+                    options.speaker_labels = text
+                        .split(',')
+                        .map(|label| label.trim().to_string())
+                        .collect();
+                    debug!("Speaker labels set to: {:?}", options.speaker_labels);
                 }
             }
             _ => continue,
         }
     }
 
+    let Some(model) = data.registry.get(model.as_deref()) else {
+        error!(
+            "Default model {} is not loaded",
+            data.registry.default_model()
+        );
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Default model is not loaded"
+        }));
+    };
+
+    // best_of would be silently ignored, whether beam search was requested
+    // or is the model's configured strategy
+    let strategy = options
+        .decoding_strategy
+        .unwrap_or(model.transcriber().config().decoding_strategy);
+    if strategy == DecodingStrategy::BeamSearch && options.best_of.is_some() {
+        warn!("best_of requested together with beam_search decoding");
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "best_of only applies to greedy decoding, use beam_size with beam_search"
+        }));
+    }
+
+    // Reject translation up front rather than failing after decoding
+    if options.task == Task::Translate && !model.info().multilingual {
+        warn!(
+            "Translation requested but model {} is English-only",
+            model.name()
+        );
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!(
+                "Translation requires a multilingual model, model {} is English-only (.en)",
+                model.name()
+            )
+        }));
+    }
+
     let audio_bytes = match audio_data {
         Some(data) => data,
         None => {
@@ -101,33 +473,72 @@ pub async fn transcribe_upload(
         }
     };
 
-    info!(
-        "Processing audio: {} bytes, {}Hz, {} channels, {} bit",
-        audio_bytes.len(),
-        sample_rate,
-        channels,
-        bit_depth
-    );
+    // An explicit encoding wins over the one implied by bit_depth
+    let resolved_encoding = match (encoding, bit_depth) {
+        (Some(name), _) => name.parse::<SampleEncoding>(),
+        (None, Some(bits)) => {
+            SampleEncoding::from_bit_depth(bits).ok_or(format!("Unsupported bit depth: {bits}"))
+        }
+        (None, None) => Ok(raw_format.encoding),
+    };
+    raw_format.encoding = match resolved_encoding {
+        Ok(encoding) => encoding,
+        Err(error_msg) => {
+            warn!("Invalid sample encoding in transcription request: {error_msg}");
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": error_msg
+            }));
+        }
+    };
 
-    // Convert raw audio bytes to f32 samples
-    let audio_samples = match convert_audio_bytes_to_samples(&audio_bytes, bit_depth) {
-        Ok(samples) => {
+    // Decode the upload, taking the format from the container header if
+    // present. Decoding and analysis are CPU-heavy for long recordings, so
+    // they run on the blocking pool rather than the request executor
+    let audio_len = audio_bytes.len();
+    let decode_result = web::block(move || {
+        decode_audio(&audio_bytes, audio_filename.as_deref(), &raw_format).map(|decoded| {
+            let audio_info = AudioInfo::analyze(&decoded);
+            (decoded, audio_info)
+        })
+    })
+    .await;
+    let (decoded, audio_info) = match decode_result {
+        Ok(Ok((decoded, audio_info))) => {
             debug!(
-                "Successfully converted {} bytes to {} samples",
-                audio_bytes.len(),
-                samples.len()
+                "Successfully decoded {} bytes to {} samples",
+                audio_len,
+                decoded.samples.len()
             );
-            samples
+            (decoded, audio_info)
         }
-        Err(error_msg) => {
-            error!("Failed to convert audio bytes to samples: {error_msg}");
+        Ok(Err(error_msg)) => {
+            error!("Failed to decode audio: {error_msg}");
             return HttpResponse::BadRequest().json(serde_json::json!({
                 "error": error_msg
             }));
         }
+        Err(e) => {
+            error!("Audio decoding task failed: {e}");
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to decode audio"
+            }));
+        }
     };
 
-    transcribe_audio_samples(&data.transcriber, audio_samples, sample_rate, channels).await
+    info!(
+        "Processing audio: {} bytes, {}, {}Hz, {} channels",
+        audio_len, decoded.format, decoded.sample_rate, decoded.channels
+    );
+
+    transcribe_audio_samples(
+        model,
+        decoded.samples,
+        decoded.sample_rate,
+        decoded.channels,
+        &options,
+        Some(audio_info),
+    )
+    .await
 }
 
 async fn read_field_data(mut field: Field) -> Result<Vec<u8>, actix_web::Error> {
@@ -139,11 +550,53 @@ async fn read_field_data(mut field: Field) -> Result<Vec<u8>, actix_web::Error>
     Ok(data)
 }
 
+async fn read_text_field(field: Field) -> Option<String> {
+    let field_data = read_field_data(field).await.ok()?;
+    String::from_utf8(field_data).ok()
+}
+
+fn parse_flag(text: &str) -> Result<bool, String> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        other => Err(format!("Invalid boolean value: {other}")),
+    }
+}
+
+fn parse_count(name: &str, text: &str) -> Result<i32, String> {
+    match text.trim().parse::<i32>() {
+        Ok(value) if (1..=MAX_DECODERS).contains(&value) => Ok(value),
+        _ => Err(format!(
+            "Invalid {name}: {} (expected an integer 1-{MAX_DECODERS})",
+            text.trim()
+        )),
+    }
+}
+
+fn parse_temperature(name: &str, text: &str) -> Result<f32, String> {
+    match text.trim().parse::<f32>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+        _ => Err(format!(
+            "Invalid {name}: {} (expected 0.0-1.0)",
+            text.trim()
+        )),
+    }
+}
+
+fn parse_threshold(name: &str, text: &str) -> Result<f32, String> {
+    match text.trim().parse::<f32>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(format!("Invalid {name}: {}", text.trim())),
+    }
+}
+
 async fn transcribe_audio_samples(
-    transcriber: &SimpleTranscriber,
+    model: &LoadedModel,
     audio_samples: Vec<f32>,
     sample_rate: u32,
     channels: usize,
+    options: &TranscribeOptions,
+    audio_info: Option<AudioInfo>,
 ) -> HttpResponse {
     if audio_samples.is_empty() {
         warn!("No audio data provided for transcription");
@@ -152,20 +605,75 @@ async fn transcribe_audio_samples(
         }));
     }
 
+    if let Err(e) = validate_layout(&audio_samples, channels) {
+        warn!("Inconsistent audio layout: {e}");
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": e.to_string()
+        }));
+    }
+
+    // Only checkable once the upload is decoded and its channel count known
+    if let (ChannelMode::Mix, Some(DownmixStrategy::Channel(channel))) =
+        (options.channel_mode, options.downmix)
+    {
+        if channels > 1 && channel >= channels {
+            warn!("Downmix channel {channel} out of range for {channels}-channel audio");
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!(
+                    "Downmix channel {channel} out of range for {channels}-channel audio"
+                )
+            }));
+        }
+    }
+
     info!(
-        "Starting transcription: {} samples, {}Hz, {} channels",
+        "Starting transcription with model {}: {} samples, {}Hz, {} channels",
+        model.name(),
         audio_samples.len(),
         sample_rate,
         channels
     );
 
-    let input_audio = InputAudio {
-        data: &audio_samples,
+    let queue = model.queue();
+    let receiver = match queue.submit(
+        model.transcriber().clone(),
+        audio_samples,
         sample_rate,
         channels,
+        options.clone(),
+    ) {
+        Ok(receiver) => receiver,
+        Err(SubmitError::Full) => {
+            warn!(
+                "Rejecting transcription, queue for model {} is full",
+                model.name()
+            );
+            return HttpResponse::ServiceUnavailable()
+                .insert_header(("Retry-After", RETRY_AFTER_SECONDS.to_string()))
+                .json(serde_json::json!({
+                    "error": format!(
+                        "Server is busy, {} transcriptions are already queued for model {}. Retry later",
+                        queue.capacity(),
+                        model.name()
+                    )
+                }));
+        }
+        Err(e) => {
+            error!("Failed to queue transcription: {e}");
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e.to_string()
+            }));
+        }
     };
 
-    match transcriber.transcribe(&input_audio) {
+    let result = match receiver.await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!(
+            "Transcription worker stopped before finishing"
+        )),
+    };
+
+    match result {
         Ok(output) => {
             info!(
                 "Transcription completed successfully: {} segments, {} characters",
@@ -181,12 +689,55 @@ async fn transcribe_audio_samples(
                     end: seg.end,
                     text: seg.text,
                     confidence: seg.confidence,
+                    channel: seg.channel,
+                    speaker: seg.speaker,
+                    words: options.word_timestamps.then(|| {
+                        seg.words
+                            .into_iter()
+                            .map(|word| TranscriptionWord {
+                                word: word.word,
+                                start: word.start,
+                                end: word.end,
+                                probability: word.probability,
+                            })
+                            .collect()
+                    }),
+                    tokens: options.verbose.then(|| {
+                        seg.tokens
+                            .into_iter()
+                            .map(|token| TranscriptionToken {
+                                text: token.text,
+                                id: token.id,
+                                p: token.p,
+                                plog: token.plog,
+                                t0: token.t0,
+                                t1: token.t1,
+                                special: token.special,
+                            })
+                            .collect()
+                    }),
                 })
                 .collect();
 
             HttpResponse::Ok().json(TranscriptionDto {
                 text: output.combined,
                 segments: Some(segments),
+                model: model.name().to_string(),
+                channel_mode: options.channel_mode,
+                task: options.task,
+                language: output.language.map(|detection| DetectedLanguage {
+                    code: detection.language,
+                    probability: detection.probability,
+                    candidates: detection
+                        .candidates
+                        .into_iter()
+                        .map(|(language, probability)| LanguageProbability {
+                            language,
+                            probability,
+                        })
+                        .collect(),
+                }),
+                audio_info,
             })
         }
         Err(e) => {
@@ -204,22 +755,25 @@ pub async fn run_server(host: String, port: u16) -> std::io::Result<()> {
 
     let config = WhisperConfig::default();
     info!(
-        "Using configuration: model_path={:?}, use_gpu={}, language={}, num_threads={}",
+        "Using configuration: model_path={:?}, use_gpu={}, language={}, num_threads={:?}",
         config.model_path, config.use_gpu, config.language, config.num_threads
     );
 
-    let transcriber = match SimpleTranscriber::new(config) {
-        Ok(t) => {
-            info!("Whisper transcriber initialized successfully");
-            t
+    let registry = match ModelRegistry::load(&config) {
+        Ok(registry) => {
+            info!(
+                "Whisper models initialized successfully: {}",
+                registry.names().join(", ")
+            );
+            registry
         }
         Err(e) => {
-            error!("Failed to initialize transcriber: {e}");
+            error!("Failed to initialize models: {e}");
             std::process::exit(1);
         }
     };
 
-    let app_state = web::Data::new(AppState { transcriber });
+    let app_state = web::Data::new(AppState { registry });
 
     info!("Starting HTTP server on {host}:{port}");
 
@@ -240,6 +794,7 @@ pub async fn run_server(host: String, port: u16) -> std::io::Result<()> {
             )
             .wrap(Logger::default())
             .service(health_check)
+            .service(list_models)
             .service(transcribe_upload)
     })
     .bind((host.as_str(), port))?
@@ -0,0 +1,215 @@
+use log::debug;
+use std::ops::Range;
+
+use crate::whisper::transcriber::Segment;
+
+/// Whisper reports segment times in 10ms units; at 16kHz that is 160 samples.
+pub const SAMPLES_PER_TIMESTAMP: usize = 160;
+
+/// Frame size used when looking for a quiet cut point (20ms at 16kHz).
+const ENERGY_FRAME_SAMPLES: usize = 320;
+
+/// Splits `audio` into windows of roughly `window_samples`, each overlapping
+/// the previous one by `overlap_samples`. Window ends are moved back to the
+/// quietest frame within the overlap so cuts land in pauses where possible.
+pub fn plan_windows(
+    audio: &[f32],
+    window_samples: usize,
+    overlap_samples: usize,
+) -> Vec<Range<usize>> {
+    let len = audio.len();
+    let mut windows = Vec::new();
+    let mut start = 0;
+
+    while start < len {
+        let nominal_end = start + window_samples;
+
+        // Fold a short tail into the current window instead of emitting a
+        // window too small to transcribe on its own
+        if nominal_end + overlap_samples + 16000 >= len {
+            windows.push(start..len);
+            break;
+        }
+
+        let search_start = nominal_end.saturating_sub(overlap_samples).max(start + 1);
+        let end = find_quietest_point(audio, search_start..nominal_end);
+        windows.push(start..end);
+
+        // Always make progress, even with an overlap larger than the window
+        start = end.saturating_sub(overlap_samples).max(start + 1);
+    }
+
+    debug!(
+        "Planned {} windows over {} samples (window={}, overlap={})",
+        windows.len(),
+        len,
+        window_samples,
+        overlap_samples
+    );
+
+    windows
+}
+
+/// Returns the end of the lowest-energy frame in `range`.
+fn find_quietest_point(audio: &[f32], range: Range<usize>) -> usize {
+    let mut best_end = range.end;
+    let mut best_energy = f32::MAX;
+    let mut frame_start = range.start;
+
+    while frame_start + ENERGY_FRAME_SAMPLES <= range.end {
+        let frame_end = frame_start + ENERGY_FRAME_SAMPLES;
+        let energy: f32 = audio[frame_start..frame_end].iter().map(|s| s * s).sum();
+        if energy < best_energy {
+            best_energy = energy;
+            best_end = frame_end;
+        }
+        frame_start = frame_end;
+    }
+
+    best_end
+}
+
+/// Shifts window-relative segment times onto the global timeline.
+pub fn offset_segments(segments: &mut [Segment], offset_samples: usize) {
+    let offset = offset_samples / SAMPLES_PER_TIMESTAMP;
+    for segment in segments {
+        segment.start += offset;
+        segment.end += offset;
+        for word in &mut segment.words {
+            word.start += offset;
+            word.end += offset;
+        }
+        for token in &mut segment.tokens {
+            token.t0 += offset;
+            token.t1 += offset;
+        }
+    }
+}
+
+/// Appends the segments of the next window, resolving the overlap with the
+/// segments already collected. Both windows transcribe the overlap, so the
+/// earlier window owns everything starting before the midpoint of the
+/// overlap and the later window everything after. A segment repeating the
+/// previous segment's text across the seam is dropped as a duplicate.
+pub fn stitch_segments(stitched: &mut Vec<Segment>, next: Vec<Segment>, overlap: Range<usize>) {
+    let boundary = (overlap.start + overlap.end) / 2 / SAMPLES_PER_TIMESTAMP;
+
+    let before = stitched.len();
+    stitched.retain(|segment| segment.start < boundary);
+    let dropped_previous = before - stitched.len();
+
+    let mut dropped_next = 0;
+    for segment in next {
+        if segment.start < boundary {
+            dropped_next += 1;
+            continue;
+        }
+        if stitched
+            .last()
+            .is_some_and(|last| normalize_text(&last.text) == normalize_text(&segment.text))
+        {
+            dropped_next += 1;
+            continue;
+        }
+        stitched.push(segment);
+    }
+
+    debug!(
+        "Stitched overlap at {boundary}: dropped {dropped_previous} trailing and {dropped_next} leading segments"
+    );
+}
+
+fn normalize_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: usize, end: usize, text: &str) -> Segment {
+        Segment {
+            start,
+            end,
+            text: text.to_string(),
+            confidence: 1.0,
+            channel: None,
+            speaker: None,
+            words: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn short_audio_is_a_single_window() {
+        let audio = vec![0.1; 16000 * 10];
+        assert_eq!(
+            plan_windows(&audio, 16000 * 30, 16000 * 5),
+            vec![0..audio.len()]
+        );
+    }
+
+    #[test]
+    fn windows_cover_audio_with_overlap() {
+        let audio = vec![0.1; 16000 * 100];
+        let windows = plan_windows(&audio, 16000 * 30, 16000 * 5);
+
+        assert!(windows.len() > 1);
+        assert_eq!(windows[0].start, 0);
+        assert_eq!(windows.last().unwrap().end, audio.len());
+        for pair in windows.windows(2) {
+            assert!(pair[1].start < pair[0].end, "windows must overlap");
+            assert!(pair[1].start > pair[0].start, "windows must advance");
+        }
+    }
+
+    #[test]
+    fn cuts_at_the_quietest_frame_in_the_overlap() {
+        let mut audio = vec![0.5; 16000 * 100];
+        // Silence 2s before the nominal end of the first window
+        let quiet = 16000 * 28;
+        audio[quiet..quiet + ENERGY_FRAME_SAMPLES].fill(0.0);
+
+        let windows = plan_windows(&audio, 16000 * 30, 16000 * 5);
+        assert_eq!(windows[0], 0..quiet + ENERGY_FRAME_SAMPLES);
+    }
+
+    #[test]
+    fn offsets_segments_onto_global_timeline() {
+        let mut segments = vec![segment(10, 20, "a")];
+        offset_segments(&mut segments, 16000);
+        assert_eq!((segments[0].start, segments[0].end), (110, 120));
+    }
+
+    #[test]
+    fn stitch_splits_overlap_at_its_midpoint() {
+        // Overlap from 20s to 30s, boundary at 25s (2500 in 10ms units)
+        let mut stitched = vec![segment(0, 1000, "first"), segment(2600, 2900, "late")];
+        let next = vec![segment(2100, 2400, "early"), segment(2600, 2900, "second")];
+
+        stitch_segments(&mut stitched, next, 16000 * 20..16000 * 30);
+
+        let texts: Vec<&str> = stitched.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn stitch_drops_repeated_text_across_the_seam() {
+        let mut stitched = vec![segment(2000, 2400, "Hello there.")];
+        let next = vec![
+            segment(2500, 2800, "hello there"),
+            segment(2800, 3000, "General Kenobi"),
+        ];
+
+        stitch_segments(&mut stitched, next, 16000 * 20..16000 * 30);
+
+        let texts: Vec<&str> = stitched.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["Hello there.", "General Kenobi"]);
+    }
+}
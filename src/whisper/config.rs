@@ -4,14 +4,76 @@ use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::whisper::decoding::{DecodingParams, DecodingStrategy, MAX_DECODERS};
+use crate::whisper::downmix::DownmixStrategy;
+use crate::whisper::preprocess::Normalization;
+use crate::whisper::resampler::ResampleQuality;
+use crate::whisper::vad::VadParams;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct WhisperConfig {
     pub model_path: PathBuf,
+    /// Directory scanned for `ggml-<name>.bin` models, each served under
+    /// its `<name>`.
+    pub models_dir: Option<PathBuf>,
+    /// Additional models as `name=path` entries.
+    pub models: Vec<String>,
+    /// Model used when a request names none.
+    pub default_model: Option<String>,
     pub use_gpu: bool,
+    /// Language code, or `auto` to detect it per recording.
     pub language: String,
+    /// How many candidate languages to report after detection.
+    pub language_top_n: usize,
     pub audio_context: i32,
     pub no_speech_threshold: f32,
-    pub num_threads: i32,
+    /// Whisper states kept ready for parallel transcriptions. Each state
+    /// holds its own KV cache and compute buffers on top of the shared model.
+    pub pool_size: usize,
+    /// Transcriptions allowed to wait for a free state before new requests
+    /// are turned away.
+    pub queue_size: usize,
+    /// Threads used by each state while it runs. `None` splits the
+    /// available cores evenly across the states of every loaded model.
+    pub num_threads: Option<i32>,
+    pub decoding_strategy: DecodingStrategy,
+    pub beam_size: i32,
+    pub best_of: i32,
+    /// Initial sampling temperature, raised by `temperature_inc` each time
+    /// a segment fails the entropy or log-probability threshold.
+    pub temperature: f32,
+    pub temperature_inc: f32,
+    pub entropy_threshold: f32,
+    pub logprob_threshold: f32,
+    /// Text fed to whisper as preceding context, e.g. to set style or
+    /// spelling.
+    pub initial_prompt: Option<String>,
+    /// Terms (product names, jargon) whisper should prefer when in doubt.
+    pub vocabulary: Vec<String>,
+    pub downmix: DownmixStrategy,
+    /// Audio longer than this is transcribed in overlapping windows. 0
+    /// disables chunking.
+    pub chunk_seconds: f32,
+    pub chunk_overlap_seconds: f32,
+    /// Skip non-speech before inference and only transcribe detected speech.
+    pub vad: bool,
+    pub vad_threshold_db: f32,
+    pub vad_min_silence_ms: u32,
+    pub vad_min_speech_ms: u32,
+    pub vad_padding_ms: u32,
+    /// Preprocessing stages applied before resampling.
+    pub preprocess_dc_removal: bool,
+    pub preprocess_highpass: bool,
+    pub preprocess_highpass_hz: f32,
+    pub preprocess_normalize: Normalization,
+    /// Target for `preprocess_normalize`. Other modes, and `None`, use the
+    /// mode's own default, see `Normalization::default_target_db`.
+    pub preprocess_target_db: Option<f32>,
+    pub preprocess_limiter: bool,
+    /// Spectral noise reduction on the 16kHz mono signal before inference.
+    pub denoise: bool,
+    pub denoise_strength: f32,
+    pub resample_quality: ResampleQuality,
 }
 
 impl Default for WhisperConfig {
@@ -23,6 +85,23 @@ impl Default for WhisperConfig {
             "./models/ggml-base.en.bin".to_string()
         });
 
+        let models_dir = std::env::var("WHISPER_MODELS_DIR")
+            .ok()
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from);
+        debug!("WHISPER_MODELS_DIR: {models_dir:?}");
+
+        let models: Vec<String> = std::env::var("WHISPER_MODELS")
+            .map(|v| parse_list(&v))
+            .unwrap_or_default();
+        debug!("WHISPER_MODELS: {models:?}");
+
+        let default_model = std::env::var("WHISPER_DEFAULT_MODEL")
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        debug!("WHISPER_DEFAULT_MODEL: {default_model:?}");
+
         let use_gpu = std::env::var("WHISPER_USE_GPU")
             .map(|v| {
                 let gpu_enabled = v.parse().unwrap_or(true);
@@ -39,6 +118,8 @@ impl Default for WhisperConfig {
             "en".to_string()
         });
 
+        let language_top_n = env_or("WHISPER_LANGUAGE_TOP_N", 5);
+
         let audio_context = std::env::var("WHISPER_AUDIO_CONTEXT")
             .map(|v| {
                 let context = v.parse().unwrap_or(768);
@@ -61,34 +142,118 @@ impl Default for WhisperConfig {
                 0.6
             });
 
-        let default_threads = std::thread::available_parallelism()
-            .map(|n| n.get() as i32)
-            .unwrap_or(4);
+        let pool_size: usize = env_or("WHISPER_POOL_SIZE", 1);
+        let queue_size: usize = env_or("WHISPER_QUEUE_SIZE", 16);
 
-        let num_threads = std::env::var("WHISPER_NUM_THREADS")
-            .map(|v| {
-                let threads = v.parse().unwrap_or(default_threads);
-                debug!("WHISPER_NUM_THREADS={v}, parsed as: {threads}");
-                threads
-            })
-            .unwrap_or_else(|_| {
-                debug!(
-                    "WHISPER_NUM_THREADS not set, defaulting to: {default_threads} (available parallelism)"
-                );
-                default_threads
-            });
+        let num_threads = match std::env::var("WHISPER_NUM_THREADS") {
+            Ok(v) => match v.trim().parse() {
+                Ok(threads) => Some(threads),
+                Err(_) => {
+                    warn!("WHISPER_NUM_THREADS={v} is invalid, splitting available cores");
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+        debug!("WHISPER_NUM_THREADS: {num_threads:?}");
+
+        let decoding_strategy = env_or("WHISPER_DECODING_STRATEGY", DecodingStrategy::Greedy);
+        let beam_size = env_or("WHISPER_BEAM_SIZE", 5);
+        let best_of = env_or("WHISPER_BEST_OF", 1);
+        let temperature = env_or("WHISPER_TEMPERATURE", 0.0);
+        let temperature_inc = env_or("WHISPER_TEMPERATURE_INC", 0.2);
+        let entropy_threshold = env_or("WHISPER_ENTROPY_THRESHOLD", 2.4);
+        let logprob_threshold = env_or("WHISPER_LOGPROB_THRESHOLD", -1.0);
+
+        let initial_prompt = std::env::var("WHISPER_INITIAL_PROMPT")
+            .ok()
+            .filter(|prompt| !prompt.trim().is_empty());
+        debug!("WHISPER_INITIAL_PROMPT: {initial_prompt:?}");
+
+        let vocabulary: Vec<String> = std::env::var("WHISPER_VOCABULARY")
+            .map(|v| parse_list(&v))
+            .unwrap_or_default();
+        debug!("WHISPER_VOCABULARY: {vocabulary:?}");
+
+        let downmix = env_or("WHISPER_DOWNMIX", DownmixStrategy::Average);
+        let chunk_seconds = env_or("WHISPER_CHUNK_SECONDS", 300.0);
+        let chunk_overlap_seconds = env_or("WHISPER_CHUNK_OVERLAP_SECONDS", 5.0);
+
+        let vad = env_or("WHISPER_VAD", false);
+        let vad_threshold_db = env_or("WHISPER_VAD_THRESHOLD_DB", -45.0);
+        let vad_min_silence_ms = env_or("WHISPER_VAD_MIN_SILENCE_MS", 500);
+        let vad_min_speech_ms = env_or("WHISPER_VAD_MIN_SPEECH_MS", 250);
+        let vad_padding_ms = env_or("WHISPER_VAD_PADDING_MS", 200);
+
+        let preprocess_dc_removal = env_or("WHISPER_PREPROCESS_DC_REMOVAL", false);
+        let preprocess_highpass = env_or("WHISPER_PREPROCESS_HIGHPASS", false);
+        let preprocess_highpass_hz = env_or("WHISPER_PREPROCESS_HIGHPASS_HZ", 80.0);
+        let preprocess_normalize = env_or("WHISPER_PREPROCESS_NORMALIZE", Normalization::Off);
+        let preprocess_target_db = match std::env::var("WHISPER_PREPROCESS_TARGET_DB") {
+            Ok(v) => match v.trim().parse::<f32>() {
+                Ok(target) => Some(target),
+                Err(_) => {
+                    warn!("WHISPER_PREPROCESS_TARGET_DB={v} is invalid, using the mode's default");
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+        debug!("WHISPER_PREPROCESS_TARGET_DB: {preprocess_target_db:?}");
+        let preprocess_limiter = env_or("WHISPER_PREPROCESS_LIMITER", false);
+
+        let denoise = env_or("WHISPER_DENOISE", false);
+        let denoise_strength = env_or("WHISPER_DENOISE_STRENGTH", 1.0);
+
+        let resample_quality = env_or("WHISPER_RESAMPLE_QUALITY", ResampleQuality::High);
 
         let config = Self {
             model_path: PathBuf::from(model_path),
+            models_dir,
+            models,
+            default_model,
             use_gpu,
             language,
+            language_top_n,
             audio_context,
             no_speech_threshold,
+            pool_size,
+            queue_size,
             num_threads,
+            decoding_strategy,
+            beam_size,
+            best_of,
+            temperature,
+            temperature_inc,
+            entropy_threshold,
+            logprob_threshold,
+            initial_prompt,
+            vocabulary,
+            downmix,
+            chunk_seconds,
+            chunk_overlap_seconds,
+            vad,
+            vad_threshold_db,
+            vad_min_silence_ms,
+            vad_min_speech_ms,
+            vad_padding_ms,
+            preprocess_dc_removal,
+            preprocess_highpass,
+            preprocess_highpass_hz,
+            preprocess_normalize,
+            preprocess_target_db,
+            preprocess_limiter,
+            denoise,
+            denoise_strength,
+            resample_quality,
         };
 
         // Validate configuration
-        if !config.model_path.exists() {
+        if let Some(dir) = &config.models_dir {
+            if !dir.is_dir() {
+                warn!("Models directory does not exist: {dir:?}");
+            }
+        } else if config.models.is_empty() && !config.model_path.exists() {
             warn!("Model path does not exist: {:?}", config.model_path);
         }
 
@@ -106,32 +271,186 @@ impl Default for WhisperConfig {
             );
         }
 
-        if config.num_threads < 1 {
+        if config.pool_size < 1 {
+            warn!(
+                "Pool size {} is invalid, should be >= 1, using 1",
+                config.pool_size
+            );
+        }
+
+        if let Some(threads) = config.num_threads.filter(|&threads| threads < 1) {
+            warn!("Number of threads {threads} is invalid, should be >= 1");
+        }
+
+        if !(1..=MAX_DECODERS).contains(&config.beam_size)
+            || !(1..=MAX_DECODERS).contains(&config.best_of)
+        {
+            warn!(
+                "Beam size {} and best_of {} should both be within 1-{MAX_DECODERS}, clamping",
+                config.beam_size, config.best_of
+            );
+        }
+
+        if config.decoding_strategy == DecodingStrategy::BeamSearch && config.best_of != 1 {
             warn!(
-                "Number of threads {} is invalid, should be >= 1",
-                config.num_threads
+                "best_of {} is ignored with beam_search decoding, it only applies to greedy",
+                config.best_of
+            );
+        }
+
+        if config.temperature < 0.0 || config.temperature_inc < 0.0 {
+            warn!(
+                "Temperature {} and temperature increment {} should not be negative",
+                config.temperature, config.temperature_inc
+            );
+        }
+
+        if config.preprocess_highpass && config.preprocess_highpass_hz <= 0.0 {
+            warn!(
+                "High-pass cutoff {}Hz is invalid, the filter will be skipped",
+                config.preprocess_highpass_hz
+            );
+        }
+
+        if config.denoise && config.denoise_strength <= 0.0 {
+            warn!(
+                "Noise reduction strength {} is not positive, noise reduction will have no effect",
+                config.denoise_strength
+            );
+        }
+
+        if config.chunk_seconds < 0.0 {
+            warn!(
+                "Chunk length {} is negative, chunking will be disabled",
+                config.chunk_seconds
+            );
+        }
+
+        if config.chunk_seconds > 0.0
+            && (config.chunk_overlap_seconds < 0.0
+                || config.chunk_overlap_seconds >= config.chunk_seconds)
+        {
+            warn!(
+                "Chunk overlap {} is outside valid range (0-{})",
+                config.chunk_overlap_seconds, config.chunk_seconds
             );
         }
 
         info!(
-            "WhisperConfig created: model_path={:?}, use_gpu={}, language={}, audio_context={}, no_speech_threshold={}, num_threads={}",
+            "WhisperConfig created: model_path={:?}, models_dir={:?}, models={:?}, default_model={:?}, use_gpu={}, language={}, audio_context={}, no_speech_threshold={}, pool_size={}, queue_size={}, num_threads={:?}, decoding=[{}], initial_prompt={}, vocabulary={} terms, downmix={}, chunk_seconds={}, chunk_overlap_seconds={}, vad={}, preprocess=[dc_removal={}, highpass={}, normalize={}, limiter={}], denoise={} (strength {}), resample_quality={}",
             config.model_path,
+            config.models_dir,
+            config.models,
+            config.default_model,
             config.use_gpu,
             config.language,
             config.audio_context,
             config.no_speech_threshold,
-            config.num_threads
+            config.pool_size,
+            config.queue_size,
+            config.num_threads,
+            config.decoding_params(),
+            config.initial_prompt.is_some(),
+            config.vocabulary.len(),
+            config.downmix,
+            config.chunk_seconds,
+            config.chunk_overlap_seconds,
+            config.vad,
+            config.preprocess_dc_removal,
+            config.preprocess_highpass,
+            config.preprocess_normalize,
+            config.preprocess_limiter,
+            config.denoise,
+            config.denoise_strength,
+            config.resample_quality
         );
 
         config
     }
 }
 
+/// Splits a comma-separated list, dropping empty entries.
+pub fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Reads and parses an environment variable, falling back to `default`
+/// when it is unset or invalid.
+fn env_or<T>(name: &str, default: T) -> T
+where
+    T: std::str::FromStr + std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(v) => match v.parse() {
+            Ok(parsed) => {
+                debug!("{name}={v}, parsed as: {parsed}");
+                parsed
+            }
+            Err(_) => {
+                warn!("{name}={v} is invalid, defaulting to: {default}");
+                default
+            }
+        },
+        Err(_) => {
+            debug!("{name} not set, defaulting to: {default}");
+            default
+        }
+    }
+}
+
 impl WhisperConfig {
     pub fn new() -> Self {
         Self::default()
     }
 
+    pub fn decoding_params(&self) -> DecodingParams {
+        DecodingParams {
+            strategy: self.decoding_strategy,
+            beam_size: self.beam_size,
+            best_of: self.best_of,
+            temperature: self.temperature,
+            temperature_inc: self.temperature_inc,
+            entropy_threshold: self.entropy_threshold,
+            logprob_threshold: self.logprob_threshold,
+        }
+    }
+
+    pub fn vad_params(&self) -> VadParams {
+        VadParams {
+            threshold_db: self.vad_threshold_db,
+            min_silence_ms: self.vad_min_silence_ms,
+            min_speech_ms: self.vad_min_speech_ms,
+            padding_ms: self.vad_padding_ms,
+        }
+    }
+
+    /// Threads per state that split the available cores evenly across
+    /// `states` whisper states, so parallel runs don't oversubscribe the
+    /// CPU.
+    pub fn default_threads(states: usize) -> i32 {
+        std::thread::available_parallelism()
+            .map(|n| (n.get() / states.max(1)).max(1) as i32)
+            .unwrap_or(4)
+    }
+
+    /// Threads used by each state, falling back to splitting the cores
+    /// across this config's own pool.
+    pub fn threads_per_state(&self) -> i32 {
+        self.num_threads
+            .unwrap_or_else(|| Self::default_threads(self.pool_size))
+            .max(1)
+    }
+
+    pub fn with_num_threads(mut self, num_threads: i32) -> Self {
+        self.num_threads = Some(num_threads);
+        info!("Updated number of threads to: {num_threads}");
+        self
+    }
+
     pub fn with_model_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
         self.model_path = path.into();
         info!("Updated model path to: {:?}", self.model_path);
@@ -149,4 +468,10 @@ impl WhisperConfig {
         info!("Updated GPU usage to: {}", self.use_gpu);
         self
     }
+
+    pub fn with_downmix(mut self, downmix: DownmixStrategy) -> Self {
+        self.downmix = downmix;
+        info!("Updated downmix strategy to: {}", self.downmix);
+        self
+    }
 }
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use whisper_rs::{FullParams, SamplingStrategy};
+
+/// whisper.cpp's `WHISPER_MAX_DECODERS`: `whisper_full` fails outright when
+/// beam search or best-of sampling asks for more decoders than this.
+pub const MAX_DECODERS: i32 = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DecodingStrategy {
+    /// Pick the most likely token at each step, keeping the best of
+    /// `best_of` samples when falling back to a higher temperature.
+    #[default]
+    Greedy,
+    /// Keep `beam_size` candidate sequences and return the most likely one.
+    /// `best_of` does not apply: whisper-rs leaves whisper.cpp's greedy
+    /// `best_of` unset for beam search, so fallback sampling uses its
+    /// default.
+    BeamSearch,
+}
+
+impl std::fmt::Display for DecodingStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Greedy => f.write_str("greedy"),
+            Self::BeamSearch => f.write_str("beam_search"),
+        }
+    }
+}
+
+impl std::str::FromStr for DecodingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "greedy" => Ok(Self::Greedy),
+            "beam_search" | "beam" | "beamsearch" => Ok(Self::BeamSearch),
+            _ => Err(format!(
+                "Invalid decoding strategy: {s} (expected greedy or beam_search)"
+            )),
+        }
+    }
+}
+
+/// Decoder settings, including whisper's temperature fallback: a segment
+/// whose entropy (whisper.cpp's stand-in for the compression ratio) is
+/// above `entropy_threshold`, or whose average log-probability is below
+/// `logprob_threshold`, is decoded again at a temperature raised by
+/// `temperature_inc`, up to 1.0.
+#[derive(Clone, Copy, Debug)]
+pub struct DecodingParams {
+    pub strategy: DecodingStrategy,
+    pub beam_size: i32,
+    pub best_of: i32,
+    pub temperature: f32,
+    /// 0 disables the fallback.
+    pub temperature_inc: f32,
+    pub entropy_threshold: f32,
+    pub logprob_threshold: f32,
+}
+
+impl DecodingParams {
+    pub fn sampling_strategy(&self) -> SamplingStrategy {
+        match self.strategy {
+            DecodingStrategy::Greedy => SamplingStrategy::Greedy {
+                best_of: self.best_of.clamp(1, MAX_DECODERS),
+            },
+            DecodingStrategy::BeamSearch => SamplingStrategy::BeamSearch {
+                beam_size: self.beam_size.clamp(1, MAX_DECODERS),
+                // Not implemented by whisper.cpp, -1 keeps its default
+                patience: -1.0,
+            },
+        }
+    }
+
+    /// Builds `FullParams` with the strategy and fallback settings applied.
+    pub fn full_params<'a, 'b>(&self) -> FullParams<'a, 'b> {
+        let mut params = FullParams::new(self.sampling_strategy());
+        params.set_temperature(self.temperature);
+        params.set_temperature_inc(self.temperature_inc);
+        params.set_entropy_thold(self.entropy_threshold);
+        params.set_logprob_thold(self.logprob_threshold);
+        params
+    }
+}
+
+impl std::fmt::Display for DecodingParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.strategy {
+            DecodingStrategy::Greedy => write!(f, "greedy (best_of={})", self.best_of)?,
+            DecodingStrategy::BeamSearch => {
+                write!(f, "beam_search (beam_size={})", self.beam_size)?
+            }
+        }
+        write!(
+            f,
+            ", temperature={} (+{} on fallback), entropy_threshold={}, logprob_threshold={}",
+            self.temperature, self.temperature_inc, self.entropy_threshold, self.logprob_threshold
+        )
+    }
+}
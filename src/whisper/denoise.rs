@@ -0,0 +1,168 @@
+use log::{debug, info, warn};
+use realfft::num_complex::Complex;
+use realfft::RealFftPlanner;
+use std::borrow::Cow;
+
+use crate::audio::analysis::{estimate_snr_db, frame_powers};
+
+/// STFT frame of 32ms at 16kHz.
+const FRAME_SAMPLES: usize = 512;
+
+/// 50% overlap, which sums to unity under the sqrt-Hann analysis and
+/// synthesis windows.
+const HOP_SAMPLES: usize = FRAME_SAMPLES / 2;
+
+/// Share of the quietest frames treated as non-speech when learning the
+/// noise profile.
+const NOISE_FRAME_RATIO: f32 = 0.2;
+
+/// Gain never drops below this, leaving a little residual noise instead of
+/// the warbling "musical noise" of a hard spectral floor.
+const SPECTRAL_FLOOR: f32 = 0.05;
+
+/// Weight of the previous frame's gain, smoothing gains over time.
+const GAIN_SMOOTHING: f32 = 0.5;
+
+/// Frame used by `snr_db` (20ms at 16kHz).
+const SNR_FRAME_SAMPLES: usize = 320;
+
+/// Suppresses stationary background noise (fans, road noise, hum) in 16kHz
+/// mono audio by spectral subtraction. The noise spectrum is learned from
+/// the quietest frames of the recording itself. `strength` scales how much
+/// of that spectrum is subtracted: 1.0 removes the estimated noise, higher
+/// values over-subtract, 0 disables the stage.
+pub fn denoise(audio: &[f32], strength: f32) -> Cow<'_, [f32]> {
+    if strength <= 0.0 || audio.len() < FRAME_SAMPLES {
+        return Cow::Borrowed(audio);
+    }
+
+    let start = std::time::Instant::now();
+    let snr_before = snr_db(audio);
+
+    let mut planner = RealFftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(FRAME_SAMPLES);
+    let inverse = planner.plan_fft_inverse(FRAME_SAMPLES);
+    let window = sqrt_hann_window();
+
+    // Pad so every sample is covered by two frames and the tail is not lost
+    let frame_count = audio.len().div_ceil(HOP_SAMPLES) + 1;
+    let mut padded = vec![0.0f32; (frame_count + 1) * HOP_SAMPLES];
+    padded[HOP_SAMPLES..HOP_SAMPLES + audio.len()].copy_from_slice(audio);
+
+    let mut input = forward.make_input_vec();
+    let mut spectra: Vec<Vec<Complex<f32>>> = Vec::with_capacity(frame_count);
+    for frame in 0..frame_count {
+        let offset = frame * HOP_SAMPLES;
+        for (i, sample) in input.iter_mut().enumerate() {
+            *sample = padded[offset + i] * window[i];
+        }
+        let mut spectrum = forward.make_output_vec();
+        if let Err(e) = forward.process(&mut input, &mut spectrum) {
+            warn!("Noise reduction FFT failed, leaving audio untouched: {e}");
+            return Cow::Borrowed(audio);
+        }
+        spectra.push(spectrum);
+    }
+
+    // Edge frames are partly zero padding and would always rank as the
+    // quietest, so only frames lying wholly inside the audio are candidates
+    let full_frames = 1..audio.len() / HOP_SAMPLES;
+    let noise_profile = learn_noise_profile(&spectra[full_frames]);
+
+    let mut output = vec![0.0f32; padded.len()];
+    let mut previous_gains = vec![1.0f32; noise_profile.len()];
+    let mut frame_output = inverse.make_output_vec();
+    for (frame, spectrum) in spectra.iter_mut().enumerate() {
+        for ((bin, noise), previous) in spectrum
+            .iter_mut()
+            .zip(&noise_profile)
+            .zip(previous_gains.iter_mut())
+        {
+            let power = bin.norm_sqr();
+            let gain = if power > 0.0 {
+                (1.0 - strength * noise / power).max(0.0).sqrt()
+            } else {
+                0.0
+            };
+            let gain =
+                (GAIN_SMOOTHING * *previous + (1.0 - GAIN_SMOOTHING) * gain).max(SPECTRAL_FLOOR);
+            *previous = gain;
+            *bin *= gain;
+        }
+
+        // DC and Nyquist bins must be purely real for the inverse transform
+        spectrum[0].im = 0.0;
+        if let Some(last) = spectrum.last_mut() {
+            last.im = 0.0;
+        }
+
+        if let Err(e) = inverse.process(spectrum, &mut frame_output) {
+            warn!("Noise reduction inverse FFT failed, leaving audio untouched: {e}");
+            return Cow::Borrowed(audio);
+        }
+
+        let offset = frame * HOP_SAMPLES;
+        for (i, sample) in frame_output.iter().enumerate() {
+            // realfft does not normalize the inverse transform
+            output[offset + i] += sample * window[i] / FRAME_SAMPLES as f32;
+        }
+    }
+
+    output.drain(..HOP_SAMPLES);
+    output.truncate(audio.len());
+
+    let snr_after = snr_db(&output);
+    debug!("Noise reduction SNR estimate: before {snr_before:.1} dB, after {snr_after:.1} dB");
+    info!(
+        "Noise reduction (strength {strength}) over {} samples took {:?}",
+        audio.len(),
+        start.elapsed()
+    );
+
+    Cow::Owned(output)
+}
+
+fn sqrt_hann_window() -> Vec<f32> {
+    (0..FRAME_SAMPLES)
+        .map(|i| {
+            let phase = std::f32::consts::PI * i as f32 / FRAME_SAMPLES as f32;
+            phase.sin()
+        })
+        .collect()
+}
+
+/// Averages the power spectra of the quietest frames, which in a speech
+/// recording are the pauses between words.
+fn learn_noise_profile(spectra: &[Vec<Complex<f32>>]) -> Vec<f32> {
+    let mut by_energy: Vec<(f32, usize)> = spectra
+        .iter()
+        .enumerate()
+        .map(|(index, spectrum)| (spectrum.iter().map(|bin| bin.norm_sqr()).sum(), index))
+        .collect();
+    by_energy.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let noise_frames = ((spectra.len() as f32 * NOISE_FRAME_RATIO) as usize).max(1);
+    let bins = spectra.first().map_or(0, |spectrum| spectrum.len());
+    let mut profile = vec![0.0f32; bins];
+    for &(_, index) in by_energy.iter().take(noise_frames) {
+        for (noise, bin) in profile.iter_mut().zip(&spectra[index]) {
+            *noise += bin.norm_sqr();
+        }
+    }
+    for noise in &mut profile {
+        *noise /= noise_frames as f32;
+    }
+
+    debug!(
+        "Learned noise profile from {noise_frames} of {} frames",
+        spectra.len()
+    );
+
+    profile
+}
+
+/// Rough signal-to-noise ratio of 16kHz mono audio in dB, estimated the
+/// same way as the `snr_db` reported in `AudioInfo`.
+fn snr_db(audio: &[f32]) -> f32 {
+    estimate_snr_db(&mut frame_powers(audio, SNR_FRAME_SAMPLES))
+}
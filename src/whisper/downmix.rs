@@ -0,0 +1,131 @@
+use anyhow::Result;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// How multichannel audio is folded into the mono signal whisper expects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DownmixStrategy {
+    /// Plain average of all channels.
+    Average,
+    /// Keep a single channel (zero-based) and drop the rest.
+    Channel(usize),
+    /// Weight each channel by its share of the total signal energy, so a
+    /// silent or near-silent channel does not halve the level of the others.
+    EnergyWeighted,
+}
+
+impl std::fmt::Display for DownmixStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Average => f.write_str("average"),
+            Self::Channel(channel) => write!(f, "channel:{channel}"),
+            Self::EnergyWeighted => f.write_str("energy"),
+        }
+    }
+}
+
+impl std::str::FromStr for DownmixStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "average" | "avg" | "mean" => Ok(Self::Average),
+            "energy" | "energy_weighted" => Ok(Self::EnergyWeighted),
+            "left" => Ok(Self::Channel(0)),
+            "right" => Ok(Self::Channel(1)),
+            other => other
+                .strip_prefix("channel:")
+                .and_then(|n| n.parse().ok())
+                .map(Self::Channel)
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid downmix strategy: {s} (expected average, energy, left, right or channel:<n>)"
+                    )
+                }),
+        }
+    }
+}
+
+/// Rejects buffers that cannot be split into whole frames.
+pub fn validate_layout(data: &[f32], channels: usize) -> Result<()> {
+    if channels == 0 {
+        error!("Invalid channel count: 0");
+        return Err(anyhow::anyhow!("Channel count must be at least 1"));
+    }
+    if !data.len().is_multiple_of(channels) {
+        error!(
+            "Sample count {} is not divisible by channel count {}",
+            data.len(),
+            channels
+        );
+        return Err(anyhow::anyhow!(
+            "Sample count {} is not divisible by channel count {}",
+            data.len(),
+            channels
+        ));
+    }
+    Ok(())
+}
+
+/// Folds interleaved audio into one channel. Mono input is passed through
+/// without a copy.
+pub fn downmix_to_mono(
+    data: &[f32],
+    channels: usize,
+    strategy: DownmixStrategy,
+) -> Result<Cow<'_, [f32]>> {
+    validate_layout(data, channels)?;
+
+    if channels == 1 {
+        debug!("Audio is already mono, skipping downmix");
+        return Ok(Cow::Borrowed(data));
+    }
+
+    let frames = data.len() / channels;
+    debug!("Downmixing {frames} frames of {channels}-channel audio using {strategy}");
+
+    let mono: Vec<f32> = match strategy {
+        DownmixStrategy::Average => {
+            let scale = 1.0 / channels as f32;
+            data.chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() * scale)
+                .collect()
+        }
+        DownmixStrategy::Channel(channel) => {
+            if channel >= channels {
+                error!("Downmix channel {channel} out of range for {channels}-channel audio");
+                return Err(anyhow::anyhow!(
+                    "Downmix channel {} out of range for {}-channel audio",
+                    channel,
+                    channels
+                ));
+            }
+            data.chunks_exact(channels)
+                .map(|frame| frame[channel])
+                .collect()
+        }
+        DownmixStrategy::EnergyWeighted => {
+            let mut energy = vec![0.0f64; channels];
+            for frame in data.chunks_exact(channels) {
+                for (ch, &sample) in frame.iter().enumerate() {
+                    energy[ch] += (sample as f64) * (sample as f64);
+                }
+            }
+            let total: f64 = energy.iter().sum();
+            let weights: Vec<f32> = if total > 0.0 {
+                energy.iter().map(|e| (e / total) as f32).collect()
+            } else {
+                vec![1.0 / channels as f32; channels]
+            };
+            debug!("Energy-weighted downmix weights: {weights:?}");
+
+            data.chunks_exact(channels)
+                .map(|frame| frame.iter().zip(&weights).map(|(s, w)| s * w).sum())
+                .collect()
+        }
+    };
+
+    Ok(Cow::Owned(mono))
+}
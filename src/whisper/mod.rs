@@ -1,3 +1,12 @@
+pub mod chunking;
 pub mod config;
+pub mod decoding;
+pub mod denoise;
+pub mod downmix;
+pub mod pool;
+pub mod preprocess;
+pub mod queue;
+pub mod registry;
 pub mod resampler;
 pub mod transcriber;
+pub mod vad;
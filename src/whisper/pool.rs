@@ -0,0 +1,111 @@
+use anyhow::Result;
+use log::{debug, error, info};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex};
+use whisper_rs::{WhisperContext, WhisperState};
+
+/// A fixed set of whisper states created from one shared context. The
+/// model weights live in the context and are shared; every state carries
+/// its own KV cache and working buffers, so states can run inference in
+/// parallel.
+pub struct StatePool {
+    states: Mutex<Vec<WhisperState>>,
+    available: Condvar,
+    size: usize,
+}
+
+impl StatePool {
+    pub fn new(ctx: &WhisperContext, size: usize) -> Result<Self> {
+        let size = size.max(1);
+        let start = std::time::Instant::now();
+
+        let states = (0..size)
+            .map(|index| {
+                ctx.create_state().map_err(|e| {
+                    error!("Failed to create whisper state {index}: {e}");
+                    anyhow::anyhow!("Failed to create whisper state: {e}")
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        info!(
+            "Created pool of {size} whisper states in {:?}",
+            start.elapsed()
+        );
+
+        Ok(Self {
+            states: Mutex::new(states),
+            available: Condvar::new(),
+            size,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Takes a state out of the pool, waiting for one to be returned if all
+    /// are busy. The state goes back to the pool when the guard is dropped.
+    pub fn acquire(&self) -> Result<PooledState<'_>> {
+        let start = std::time::Instant::now();
+
+        let mut states = self.states.lock().map_err(|_| {
+            error!("Failed to acquire whisper state pool lock");
+            anyhow::anyhow!("Failed to acquire whisper state pool lock")
+        })?;
+        while states.is_empty() {
+            states = self.available.wait(states).map_err(|_| {
+                error!("Failed to wait for a whisper state");
+                anyhow::anyhow!("Failed to wait for a whisper state")
+            })?;
+        }
+        let state = states.pop();
+
+        debug!(
+            "Acquired whisper state after {:?}, {} of {} idle",
+            start.elapsed(),
+            states.len(),
+            self.size
+        );
+
+        Ok(PooledState { state, pool: self })
+    }
+}
+
+pub struct PooledState<'a> {
+    state: Option<WhisperState>,
+    pool: &'a StatePool,
+}
+
+impl Deref for PooledState<'_> {
+    type Target = WhisperState;
+
+    fn deref(&self) -> &Self::Target {
+        self.state
+            .as_ref()
+            .expect("pooled state is present until drop")
+    }
+}
+
+impl DerefMut for PooledState<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.state
+            .as_mut()
+            .expect("pooled state is present until drop")
+    }
+}
+
+impl Drop for PooledState<'_> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            // A poisoned lock only means another thread panicked while
+            // holding it, the state list itself is still consistent
+            let mut states = match self.pool.states.lock() {
+                Ok(states) => states,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            states.push(state);
+            self.pool.available.notify_one();
+        }
+    }
+}
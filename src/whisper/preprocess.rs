@@ -0,0 +1,388 @@
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use crate::audio::analysis::{amplitude_to_db, power_to_db};
+
+/// Normalization never boosts by more than this, so near-silent input is
+/// not blown up into pure noise.
+const MAX_GAIN_DB: f32 = 30.0;
+
+/// Ceiling the limiter holds peaks under.
+const LIMITER_CEILING_DB: f32 = -1.0;
+
+/// Limiter release time constant.
+const LIMITER_RELEASE_MS: f64 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Normalization {
+    Off,
+    /// Scale so the highest sample peak hits the target level in dBFS.
+    Peak,
+    /// Scale so the overall RMS level hits the target level in dBFS.
+    Rms,
+    /// Scale so the gated, K-weighted integrated loudness (EBU R128 /
+    /// ITU-R BS.1770) hits the target level in LUFS.
+    Loudness,
+}
+
+impl Normalization {
+    /// Target used when none is configured: near full scale for peak, a
+    /// typical speech level for RMS and the EBU R128 broadcast level for
+    /// loudness.
+    pub fn default_target_db(&self) -> f32 {
+        match self {
+            Self::Off => 0.0,
+            Self::Peak => -1.0,
+            Self::Rms => -20.0,
+            Self::Loudness => -23.0,
+        }
+    }
+}
+
+impl std::fmt::Display for Normalization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => f.write_str("off"),
+            Self::Peak => f.write_str("peak"),
+            Self::Rms => f.write_str("rms"),
+            Self::Loudness => f.write_str("loudness"),
+        }
+    }
+}
+
+impl std::str::FromStr for Normalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" | "false" => Ok(Self::Off),
+            "peak" => Ok(Self::Peak),
+            "rms" => Ok(Self::Rms),
+            "loudness" | "lufs" | "r128" | "ebu" => Ok(Self::Loudness),
+            _ => Err(format!(
+                "Invalid normalization: {s} (expected off, peak, rms or loudness)"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PreprocessParams {
+    pub dc_removal: bool,
+    /// High-pass cutoff in Hz, `None` disables the filter.
+    pub highpass_hz: Option<f32>,
+    pub normalize: Normalization,
+    /// Target level for normalization, dBFS for peak/RMS and LUFS for
+    /// loudness.
+    pub target_db: f32,
+    pub limiter: bool,
+}
+
+impl PreprocessParams {
+    pub fn is_noop(&self) -> bool {
+        !self.dc_removal
+            && self.highpass_hz.is_none()
+            && self.normalize == Normalization::Off
+            && !self.limiter
+    }
+}
+
+/// Runs the enabled stages over interleaved audio at its original sample
+/// rate, in order: DC removal, high-pass, normalization, limiter. Returns
+/// the input untouched when every stage is disabled.
+pub fn preprocess<'a>(
+    audio: &'a [f32],
+    channels: usize,
+    sample_rate: u32,
+    params: &PreprocessParams,
+) -> Cow<'a, [f32]> {
+    if params.is_noop() || audio.is_empty() || channels == 0 {
+        return Cow::Borrowed(audio);
+    }
+
+    let start = std::time::Instant::now();
+    let mut output = audio.to_vec();
+
+    if params.dc_removal {
+        remove_dc_offset(&mut output, channels);
+    }
+
+    if let Some(cutoff) = params.highpass_hz {
+        high_pass(&mut output, channels, sample_rate, cutoff);
+    }
+
+    if params.normalize != Normalization::Off {
+        normalize(
+            &mut output,
+            channels,
+            sample_rate,
+            params.normalize,
+            params.target_db,
+        );
+    }
+
+    if params.limiter {
+        limit(&mut output, channels, sample_rate, LIMITER_CEILING_DB);
+    }
+
+    info!(
+        "Preprocessed {} samples in {:?} (dc_removal={}, highpass={:?}, normalize={}, limiter={})",
+        audio.len(),
+        start.elapsed(),
+        params.dc_removal,
+        params.highpass_hz,
+        params.normalize,
+        params.limiter
+    );
+
+    Cow::Owned(output)
+}
+
+fn remove_dc_offset(audio: &mut [f32], channels: usize) {
+    let frames = audio.len() / channels;
+    if frames == 0 {
+        return;
+    }
+
+    for ch in 0..channels {
+        let mean = audio
+            .iter()
+            .skip(ch)
+            .step_by(channels)
+            .map(|&s| s as f64)
+            .sum::<f64>()
+            / frames as f64;
+        debug!("Channel {ch} DC offset: {mean:.6}");
+        for sample in audio.iter_mut().skip(ch).step_by(channels) {
+            *sample -= mean as f32;
+        }
+    }
+}
+
+/// Direct form I biquad section.
+#[derive(Clone, Copy)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    /// Second-order Butterworth high-pass (RBJ audio EQ cookbook).
+    fn high_pass(sample_rate: f64, cutoff: f64) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let alpha = w0.sin() / (2.0 * std::f64::consts::FRAC_1_SQRT_2);
+        let cos_w0 = w0.cos();
+        Self::new(
+            [(1.0 + cos_w0) / 2.0, -(1.0 + cos_w0), (1.0 + cos_w0) / 2.0],
+            [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+        )
+    }
+
+    /// High-shelf stage of the BS.1770 K-weighting filter.
+    fn k_weighting_shelf(sample_rate: f64) -> Self {
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Self::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    /// RLB high-pass stage of the BS.1770 K-weighting filter.
+    fn k_weighting_high_pass(sample_rate: f64) -> Self {
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Self::new(
+            [1.0, -2.0, 1.0],
+            [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+fn high_pass(audio: &mut [f32], channels: usize, sample_rate: u32, cutoff: f32) {
+    let nyquist = sample_rate as f32 / 2.0;
+    if cutoff <= 0.0 || cutoff >= nyquist {
+        debug!("High-pass cutoff {cutoff}Hz outside (0, {nyquist}Hz), skipping");
+        return;
+    }
+
+    debug!("Applying {cutoff}Hz high-pass filter");
+    let mut filters = vec![Biquad::high_pass(sample_rate as f64, cutoff as f64); channels];
+    for frame in audio.chunks_exact_mut(channels) {
+        for (sample, filter) in frame.iter_mut().zip(filters.iter_mut()) {
+            *sample = filter.process(*sample as f64) as f32;
+        }
+    }
+}
+
+fn normalize(
+    audio: &mut [f32],
+    channels: usize,
+    sample_rate: u32,
+    mode: Normalization,
+    target_db: f32,
+) {
+    let measured_db = match mode {
+        Normalization::Off => return,
+        Normalization::Peak => {
+            let peak = audio.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+            amplitude_to_db(peak)
+        }
+        Normalization::Rms => {
+            let mean_square =
+                audio.iter().map(|&s| (s as f64) * (s as f64)).sum::<f64>() / audio.len() as f64;
+            power_to_db(mean_square as f32)
+        }
+        Normalization::Loudness => integrated_loudness(audio, channels, sample_rate),
+    };
+
+    if !measured_db.is_finite() || measured_db <= -120.0 {
+        debug!("Audio is silent, skipping {mode} normalization");
+        return;
+    }
+
+    let gain_db = (target_db - measured_db).min(MAX_GAIN_DB);
+    let gain = 10f32.powf(gain_db / 20.0);
+    debug!(
+        "{mode} normalization: measured {measured_db:.1} dB, target {target_db:.1} dB, applying {gain_db:+.1} dB"
+    );
+
+    for sample in audio.iter_mut() {
+        *sample *= gain;
+    }
+}
+
+/// Gated integrated loudness per ITU-R BS.1770-4, in LUFS. Every channel
+/// is weighted equally.
+fn integrated_loudness(audio: &[f32], channels: usize, sample_rate: u32) -> f32 {
+    let mut shelves = vec![Biquad::k_weighting_shelf(sample_rate as f64); channels];
+    let mut high_passes = vec![Biquad::k_weighting_high_pass(sample_rate as f64); channels];
+
+    // Squared K-weighted samples, summed over channels per frame
+    let weighted: Vec<f64> = audio
+        .chunks_exact(channels)
+        .map(|frame| {
+            frame
+                .iter()
+                .enumerate()
+                .map(|(ch, &s)| {
+                    let y = high_passes[ch].process(shelves[ch].process(s as f64));
+                    y * y
+                })
+                .sum()
+        })
+        .collect();
+
+    // 400ms blocks with 75% overlap
+    let block = (sample_rate as usize * 4 / 10).max(1);
+    let step = (block / 4).max(1);
+    let mut block_powers = Vec::new();
+    let mut start = 0;
+    while start + block <= weighted.len() {
+        block_powers.push(weighted[start..start + block].iter().sum::<f64>() / block as f64);
+        start += step;
+    }
+    if block_powers.is_empty() && !weighted.is_empty() {
+        block_powers.push(weighted.iter().sum::<f64>() / weighted.len() as f64);
+    }
+
+    let loudness = |power: f64| -0.691 + 10.0 * power.max(1e-20).log10();
+    let gated_mean = |threshold: f64| {
+        let gated: Vec<f64> = block_powers
+            .iter()
+            .copied()
+            .filter(|&p| loudness(p) > threshold)
+            .collect();
+        if gated.is_empty() {
+            None
+        } else {
+            Some(gated.iter().sum::<f64>() / gated.len() as f64)
+        }
+    };
+
+    // Absolute gate at -70 LUFS, then relative gate 10 LU below that
+    let Some(absolute) = gated_mean(-70.0) else {
+        return f32::NEG_INFINITY;
+    };
+    let relative_threshold = loudness(absolute) - 10.0;
+    let integrated = gated_mean(relative_threshold.max(-70.0)).unwrap_or(absolute);
+
+    loudness(integrated) as f32
+}
+
+/// Peak limiter with instant attack and exponential release. The gain is
+/// linked across channels so the stereo image does not shift.
+fn limit(audio: &mut [f32], channels: usize, sample_rate: u32, ceiling_db: f32) {
+    let ceiling = 10f32.powf(ceiling_db / 20.0);
+    let release = (-1.0 / (LIMITER_RELEASE_MS / 1000.0 * sample_rate as f64)).exp() as f32;
+
+    let mut envelope = 1.0f32;
+    let mut limited_frames = 0usize;
+
+    for frame in audio.chunks_exact_mut(channels) {
+        let peak = frame.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        let wanted = if peak > ceiling { ceiling / peak } else { 1.0 };
+
+        envelope = if wanted < envelope {
+            wanted
+        } else {
+            wanted + (envelope - wanted) * release
+        };
+
+        if envelope < 1.0 {
+            limited_frames += 1;
+            for sample in frame.iter_mut() {
+                *sample *= envelope;
+            }
+        }
+    }
+
+    debug!("Limiter reduced gain on {limited_frames} frames (ceiling {ceiling_db} dBFS)");
+}
@@ -0,0 +1,187 @@
+use anyhow::Result;
+use log::{debug, error, info, warn};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+use crate::whisper::transcriber::{
+    InputAudio, SimpleTranscriber, TranscribeOptions, TranscribeOutput,
+};
+
+/// A transcription waiting for a worker, owning everything it needs.
+struct Job {
+    transcriber: SimpleTranscriber,
+    audio: Vec<f32>,
+    sample_rate: u32,
+    channels: usize,
+    options: TranscribeOptions,
+    reply: oneshot::Sender<Result<TranscribeOutput>>,
+}
+
+#[derive(Debug)]
+pub enum SubmitError {
+    /// Every worker is busy and the queue is at capacity.
+    Full,
+    /// The workers have shut down.
+    Stopped,
+}
+
+impl std::fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full => f.write_str("Transcription queue is full"),
+            Self::Stopped => f.write_str("Transcription workers have stopped"),
+        }
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
+/// Runs transcriptions on dedicated OS threads so inference never blocks
+/// the async runtime. Jobs wait in a bounded queue; once it is full new
+/// jobs are rejected instead of piling up. Each model gets its own queue
+/// with one worker per whisper state, so a worker never waits on a state
+/// of a model that is busy with someone else's jobs.
+pub struct InferenceQueue {
+    name: String,
+    sender: SyncSender<Job>,
+    /// Jobs submitted and not yet picked up by a worker.
+    queued: Arc<AtomicUsize>,
+    capacity: usize,
+}
+
+impl InferenceQueue {
+    pub fn new(name: &str, workers: usize, capacity: usize) -> Result<Self> {
+        let workers = workers.max(1);
+        let (sender, receiver) = mpsc::sync_channel::<Job>(capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queued = Arc::new(AtomicUsize::new(0));
+
+        for index in 0..workers {
+            let receiver = Arc::clone(&receiver);
+            let queued = Arc::clone(&queued);
+            std::thread::Builder::new()
+                .name(format!("whisper-{name}-{index}"))
+                .spawn(move || run_worker(index, &receiver, &queued))
+                .map_err(|e| {
+                    error!("Failed to spawn transcription worker {index} for {name}: {e}");
+                    anyhow::anyhow!("Failed to spawn transcription worker: {e}")
+                })?;
+        }
+
+        info!("Started {workers} transcription workers for {name} with a queue of {capacity}");
+
+        Ok(Self {
+            name: name.to_string(),
+            sender,
+            queued,
+            capacity,
+        })
+    }
+
+    /// Queues a transcription and returns a receiver for its result, or
+    /// `SubmitError::Full` straight away if there is no room.
+    pub fn submit(
+        &self,
+        transcriber: SimpleTranscriber,
+        audio: Vec<f32>,
+        sample_rate: u32,
+        channels: usize,
+        options: TranscribeOptions,
+    ) -> Result<oneshot::Receiver<Result<TranscribeOutput>>, SubmitError> {
+        let (reply, receiver) = oneshot::channel();
+        let job = Job {
+            transcriber,
+            audio,
+            sample_rate,
+            channels,
+            options,
+            reply,
+        };
+
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        match self.sender.try_send(job) {
+            Ok(()) => {
+                debug!(
+                    "Queued transcription for {}, {} of {} queue slots in use",
+                    self.name,
+                    self.queued(),
+                    self.capacity
+                );
+                Ok(receiver)
+            }
+            Err(TrySendError::Full(_)) => {
+                self.queued.fetch_sub(1, Ordering::SeqCst);
+                warn!(
+                    "Transcription queue for {} is full ({} jobs)",
+                    self.name, self.capacity
+                );
+                Err(SubmitError::Full)
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                self.queued.fetch_sub(1, Ordering::SeqCst);
+                error!("Transcription workers for {} have stopped", self.name);
+                Err(SubmitError::Stopped)
+            }
+        }
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+fn run_worker(index: usize, receiver: &Mutex<Receiver<Job>>, queued: &AtomicUsize) {
+    debug!("Transcription worker {index} started");
+
+    loop {
+        // Only hold the lock while waiting, so other workers can pick up
+        // the next job while this one runs
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => {
+                error!("Transcription worker {index} lost the queue lock, stopping");
+                return;
+            }
+        };
+        let Ok(job) = job else {
+            debug!("Transcription queue closed, worker {index} stopping");
+            return;
+        };
+        queued.fetch_sub(1, Ordering::SeqCst);
+
+        if job.reply.is_closed() {
+            debug!("Worker {index}: client went away while queued, skipping transcription");
+            continue;
+        }
+
+        let start = std::time::Instant::now();
+        let input_audio = InputAudio {
+            data: &job.audio,
+            sample_rate: job.sample_rate,
+            channels: job.channels,
+        };
+        // A panic inside inference must not take the worker down with it
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            job.transcriber.transcribe(&input_audio, &job.options)
+        }))
+        .unwrap_or_else(|_| {
+            error!("Worker {index}: transcription panicked");
+            Err(anyhow::anyhow!("Transcription panicked"))
+        });
+        debug!(
+            "Worker {index} finished transcription in {:?}",
+            start.elapsed()
+        );
+
+        if job.reply.send(result).is_err() {
+            debug!("Worker {index}: client went away before the result was ready");
+        }
+    }
+}
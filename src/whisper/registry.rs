@@ -0,0 +1,308 @@
+use anyhow::Result;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::whisper::config::WhisperConfig;
+use crate::whisper::queue::InferenceQueue;
+use crate::whisper::transcriber::SimpleTranscriber;
+
+/// Metadata of a loaded model, as listed by the models endpoint.
+#[derive(Clone, Debug, Serialize)]
+pub struct ModelInfo {
+    pub name: String,
+    /// File name only, the models endpoint must not reveal server paths.
+    pub file: String,
+    pub size_bytes: u64,
+    /// Size class reported by whisper.cpp, e.g. `tiny` or `large`.
+    pub model_type: String,
+    /// English-only (`.en`) models can neither detect languages nor
+    /// translate.
+    pub multilingual: bool,
+    pub vocab_size: i32,
+    pub audio_layers: i32,
+    pub text_layers: i32,
+    pub mel_bins: i32,
+    /// Transcriptions the model can run at once.
+    pub parallel_states: usize,
+    pub default: bool,
+}
+
+/// Where a model is loaded from. Failing to load a required model stops
+/// startup, other models are skipped with a warning.
+struct ModelSource {
+    path: PathBuf,
+    required: bool,
+}
+
+pub struct LoadedModel {
+    transcriber: SimpleTranscriber,
+    queue: InferenceQueue,
+    info: ModelInfo,
+}
+
+impl LoadedModel {
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    pub fn transcriber(&self) -> &SimpleTranscriber {
+        &self.transcriber
+    }
+
+    pub fn info(&self) -> &ModelInfo {
+        &self.info
+    }
+
+    /// Queue feeding this model's own workers, one per whisper state.
+    pub fn queue(&self) -> &InferenceQueue {
+        &self.queue
+    }
+}
+
+/// Named models loaded side by side, e.g. a small model for interactive
+/// dictation next to a large one for archival jobs. Every model gets its
+/// own context, state pool and inference queue built from the same
+/// `WhisperConfig`, so jobs for one model never wait behind another's.
+pub struct ModelRegistry {
+    models: BTreeMap<String, LoadedModel>,
+    default_model: String,
+}
+
+impl ModelRegistry {
+    /// Loads every model in `models_dir` and every `name=path` entry of
+    /// `models`, with entries overriding directory models of the same
+    /// name. Without either, only `model_path` is loaded. A directory model
+    /// that fails to load is skipped, while explicitly configured models and
+    /// the configured default must load.
+    pub fn load(config: &WhisperConfig) -> Result<Self> {
+        let mut sources: BTreeMap<String, ModelSource> = BTreeMap::new();
+
+        if let Some(dir) = &config.models_dir {
+            for (name, path) in discover_models(dir)? {
+                sources.insert(
+                    name,
+                    ModelSource {
+                        path,
+                        required: false,
+                    },
+                );
+            }
+        }
+
+        for entry in &config.models {
+            let (name, path) = parse_model_entry(entry)?;
+            let source = ModelSource {
+                path,
+                required: true,
+            };
+            if let Some(previous) = sources.insert(name.clone(), source) {
+                info!(
+                    "Model {name} from WHISPER_MODELS replaces {:?}",
+                    previous.path
+                );
+            }
+        }
+
+        if config.models_dir.is_none() && config.models.is_empty() {
+            let name = model_name(&config.model_path).unwrap_or_else(|| "default".to_string());
+            sources.insert(
+                name,
+                ModelSource {
+                    path: config.model_path.clone(),
+                    required: true,
+                },
+            );
+        }
+
+        if sources.is_empty() {
+            error!("No models found to load");
+            return Err(anyhow::anyhow!(
+                "No models found, check WHISPER_MODELS_DIR and WHISPER_MODELS"
+            ));
+        }
+
+        if let Some(name) = &config.default_model {
+            match sources.get_mut(name) {
+                Some(source) => source.required = true,
+                None => {
+                    error!("Default model {name} is not among the configured models");
+                    return Err(anyhow::anyhow!(
+                        "Default model {name} is not among the configured models: {}",
+                        sources.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ));
+                }
+            }
+        }
+
+        info!(
+            "Loading {} models: {}",
+            sources.len(),
+            sources.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+
+        // All models run at once, so the cores are split across every state
+        // of every model rather than per model
+        let total_states = sources.len() * config.pool_size.max(1);
+        let threads = config
+            .num_threads
+            .unwrap_or_else(|| WhisperConfig::default_threads(total_states));
+        info!("Using {threads} threads per state across {total_states} whisper states");
+
+        let mut models = BTreeMap::new();
+        for (name, source) in sources {
+            let model_config = config
+                .clone()
+                .with_model_path(&source.path)
+                .with_num_threads(threads);
+            let transcriber = match SimpleTranscriber::new(model_config) {
+                Ok(transcriber) => transcriber,
+                Err(e) if !source.required => {
+                    warn!("Skipping model {name} from {:?}: {e}", source.path);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let queue = InferenceQueue::new(&name, transcriber.pool_size(), config.queue_size)?;
+            let info = describe(&name, &source.path, &transcriber);
+            info!(
+                "Loaded model {name}: {} ({}), {} states",
+                info.model_type,
+                if info.multilingual {
+                    "multilingual"
+                } else {
+                    "English-only"
+                },
+                info.parallel_states
+            );
+            models.insert(
+                name,
+                LoadedModel {
+                    transcriber,
+                    queue,
+                    info,
+                },
+            );
+        }
+
+        // Without a configured default, the first model that loaded is used
+        let Some(default_model) = config
+            .default_model
+            .clone()
+            .or_else(|| models.keys().next().cloned())
+        else {
+            error!("None of the configured models could be loaded");
+            return Err(anyhow::anyhow!(
+                "None of the configured models could be loaded"
+            ));
+        };
+        if let Some(model) = models.get_mut(&default_model) {
+            model.info.default = true;
+        }
+
+        info!("Default model: {default_model}");
+
+        Ok(Self {
+            models,
+            default_model,
+        })
+    }
+
+    /// Looks up a model by name, or the default model for `None`.
+    pub fn get(&self, name: Option<&str>) -> Option<&LoadedModel> {
+        self.models.get(name.unwrap_or(&self.default_model))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.models.contains_key(name)
+    }
+
+    pub fn default_model(&self) -> &str {
+        &self.default_model
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.models.keys().map(String::as_str).collect()
+    }
+
+    pub fn models(&self) -> Vec<&ModelInfo> {
+        self.models.values().map(|model| &model.info).collect()
+    }
+}
+
+/// Derives a model's name from its file name: `ggml-tiny.en.bin` is served
+/// as `tiny.en`, the naming used by `open-transcribe download`.
+pub fn model_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let name = file_name.strip_suffix(".bin").unwrap_or(file_name);
+    let name = name.strip_prefix("ggml-").unwrap_or(name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Finds `*.bin` model files in `dir`, skipping anything else.
+fn discover_models(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let entries = std::fs::read_dir(dir).map_err(|e| {
+        error!("Failed to read models directory {dir:?}: {e}");
+        anyhow::anyhow!("Failed to read models directory {dir:?}: {e}")
+    })?;
+
+    let mut models = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|ext| ext.to_str()) != Some("bin") {
+            debug!("Skipping {path:?}, not a model file");
+            continue;
+        }
+        match model_name(&path) {
+            Some(name) => {
+                debug!("Found model {name} at {path:?}");
+                models.push((name, path));
+            }
+            None => warn!("Skipping model file with unusable name: {path:?}"),
+        }
+    }
+
+    if models.is_empty() {
+        warn!("No model files (*.bin) found in {dir:?}");
+    }
+
+    Ok(models)
+}
+
+/// Parses a `name=path` entry of `WHISPER_MODELS`.
+fn parse_model_entry(entry: &str) -> Result<(String, PathBuf)> {
+    match entry.split_once('=') {
+        Some((name, path)) if !name.trim().is_empty() && !path.trim().is_empty() => {
+            Ok((name.trim().to_string(), PathBuf::from(path.trim())))
+        }
+        _ => {
+            error!("Invalid model entry: {entry}");
+            Err(anyhow::anyhow!(
+                "Invalid model entry: {entry} (expected name=path)"
+            ))
+        }
+    }
+}
+
+fn describe(name: &str, path: &Path, transcriber: &SimpleTranscriber) -> ModelInfo {
+    let ctx = transcriber.context();
+    ModelInfo {
+        name: name.to_string(),
+        file: path
+            .file_name()
+            .map(|file| file.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        size_bytes: std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0),
+        model_type: ctx
+            .model_type_readable()
+            .unwrap_or_else(|_| "unknown".to_string()),
+        multilingual: ctx.is_multilingual(),
+        vocab_size: ctx.model_n_vocab(),
+        audio_layers: ctx.model_n_audio_layer(),
+        text_layers: ctx.model_n_text_layer(),
+        mel_bins: ctx.model_n_mels(),
+        parallel_states: transcriber.pool_size(),
+        default: false,
+    }
+}
@@ -1,22 +1,293 @@
 use anyhow::Result;
 use log::{debug, info, warn};
-use rubato::{Resampler, SincFixedIn, SincInterpolationType, WindowFunction};
+use rubato::{
+    FastFixedIn, FftFixedIn, PolynomialDegree, SincFixedIn, SincInterpolationParameters,
+    SincInterpolationType, VecResampler, WindowFunction,
+};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
+/// Input frames handed to rubato per call. Memory use of the resampler is
+/// bounded by this, not by the length of the input.
+const CHUNK_FRAMES: usize = 1024;
+
+/// Trade-off between resampling cost and fidelity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ResampleQuality {
+    /// Cubic interpolation when upsampling (e.g. 8kHz telephony), FFT
+    /// resampling when downsampling. Cheapest, fine for speech.
+    Fast,
+    /// Sinc interpolation with a short filter.
+    Balanced,
+    /// Sinc interpolation with a long, heavily oversampled filter.
+    #[default]
+    High,
+}
+
+impl ResampleQuality {
+    fn sinc_parameters(self) -> SincInterpolationParameters {
+        match self {
+            Self::Balanced => SincInterpolationParameters {
+                sinc_len: 64,
+                f_cutoff: 0.91,
+                interpolation: SincInterpolationType::Linear,
+                oversampling_factor: 128,
+                window: WindowFunction::Blackman2,
+            },
+            _ => SincInterpolationParameters {
+                sinc_len: 128,
+                f_cutoff: 0.95,
+                interpolation: SincInterpolationType::Linear,
+                oversampling_factor: 256,
+                window: WindowFunction::BlackmanHarris2,
+            },
+        }
+    }
+
+    fn build(
+        self,
+        input_rate: u32,
+        output_rate: u32,
+        channels: usize,
+    ) -> Result<Box<dyn VecResampler<f32>>> {
+        let ratio = output_rate as f64 / input_rate as f64;
+        let resampler: Box<dyn VecResampler<f32>> = match self {
+            Self::Fast if output_rate > input_rate => {
+                debug!("Resampler: cubic polynomial interpolation");
+                Box::new(FastFixedIn::<f32>::new(
+                    ratio,
+                    1.0,
+                    PolynomialDegree::Cubic,
+                    CHUNK_FRAMES,
+                    channels,
+                )?)
+            }
+            Self::Fast => {
+                debug!("Resampler: synchronous FFT");
+                Box::new(FftFixedIn::<f32>::new(
+                    input_rate as usize,
+                    output_rate as usize,
+                    CHUNK_FRAMES,
+                    2,
+                    channels,
+                )?)
+            }
+            Self::Balanced | Self::High => {
+                let params = self.sinc_parameters();
+                debug!(
+                    "Resampler: sinc_len={}, f_cutoff={}, oversampling_factor={}",
+                    params.sinc_len, params.f_cutoff, params.oversampling_factor
+                );
+                Box::new(SincFixedIn::<f32>::new(
+                    ratio,
+                    1.0,
+                    params,
+                    CHUNK_FRAMES,
+                    channels,
+                )?)
+            }
+        };
+        Ok(resampler)
+    }
+}
+
+impl std::fmt::Display for ResampleQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fast => f.write_str("fast"),
+            Self::Balanced => f.write_str("balanced"),
+            Self::High => f.write_str("high"),
+        }
+    }
+}
+
+impl std::str::FromStr for ResampleQuality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fast" | "low" => Ok(Self::Fast),
+            "balanced" | "medium" => Ok(Self::Balanced),
+            "high" | "best" => Ok(Self::High),
+            _ => Err(format!(
+                "Invalid resample quality: {s} (expected fast, balanced or high)"
+            )),
+        }
+    }
+}
+
+/// Resamples interleaved audio in fixed-size chunks. Input can be fed in
+/// pieces of any length, e.g. straight from a live capture, and output is
+/// returned as soon as a chunk has been processed. The resampler's delay is
+/// trimmed from the start of the output, so the output lines up with the
+/// input sample for sample.
+pub struct StreamingResampler {
+    resampler: Box<dyn VecResampler<f32>>,
+    channels: usize,
+    ratio: f64,
+    /// Deinterleaved input waiting for a full chunk.
+    pending: Vec<Vec<f32>>,
+    output_buffer: Vec<Vec<f32>>,
+    /// Output frames still to be dropped to compensate for the delay.
+    delay_remaining: usize,
+    input_frames: usize,
+    output_frames: usize,
+}
+
+impl StreamingResampler {
+    pub fn new(
+        input_rate: u32,
+        output_rate: u32,
+        channels: usize,
+        quality: ResampleQuality,
+    ) -> Result<Self> {
+        if input_rate == 0 || output_rate == 0 || channels == 0 {
+            return Err(anyhow::anyhow!(
+                "Invalid resampler setup: {input_rate}Hz -> {output_rate}Hz, {channels} channels"
+            ));
+        }
+
+        let ratio = output_rate as f64 / input_rate as f64;
+        debug!("Resample ratio: {ratio:.6} ({input_rate}Hz -> {output_rate}Hz, {quality} quality)");
+
+        let resampler_start = std::time::Instant::now();
+        let resampler = quality.build(input_rate, output_rate, channels)?;
+        debug!("Created resampler in {:?}", resampler_start.elapsed());
+
+        // The sinc resamplers start with their kernel centred on the first
+        // input frame, so what `output_delay` reports has already been
+        // compensated for and trimming it would cut the start of the audio
+        let delay_remaining = match quality {
+            ResampleQuality::Fast => resampler.output_delay(),
+            ResampleQuality::Balanced | ResampleQuality::High => 0,
+        };
+        let output_buffer = resampler.output_buffer_allocate(true);
+
+        Ok(Self {
+            resampler,
+            channels,
+            ratio,
+            pending: vec![Vec::with_capacity(CHUNK_FRAMES); channels],
+            output_buffer,
+            delay_remaining,
+            input_frames: 0,
+            output_frames: 0,
+        })
+    }
+
+    /// Feeds interleaved samples and appends whatever output is ready to
+    /// `output`. Incomplete chunks are kept until the next call or `finish`.
+    pub fn process_into(&mut self, input: &[f32], output: &mut Vec<f32>) -> Result<()> {
+        if !input.len().is_multiple_of(self.channels) {
+            warn!(
+                "Resampler input of {} samples is not a whole number of {}-channel frames, dropping the remainder",
+                input.len(),
+                self.channels
+            );
+        }
+
+        for frame in input.chunks_exact(self.channels) {
+            for (channel, &sample) in self.pending.iter_mut().zip(frame) {
+                channel.push(sample);
+            }
+            self.input_frames += 1;
+
+            if self.pending[0].len() >= self.resampler.input_frames_next() {
+                let (_, produced) = self.resampler.process_into_buffer(
+                    &self.pending,
+                    &mut self.output_buffer,
+                    None,
+                )?;
+                for channel in &mut self.pending {
+                    channel.clear();
+                }
+                self.emit(produced, usize::MAX, output);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Convenience wrapper around `process_into` returning a fresh buffer.
+    pub fn process(&mut self, input: &[f32]) -> Result<Vec<f32>> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output)?;
+        Ok(output)
+    }
+
+    /// Flushes buffered input and the resampler's delay line, appending the
+    /// remaining output. Total output is the input length scaled by the
+    /// resampling ratio.
+    pub fn finish(&mut self, output: &mut Vec<f32>) -> Result<()> {
+        let expected = (self.input_frames as f64 * self.ratio) as usize;
+
+        if !self.pending[0].is_empty() {
+            let (_, produced) = self.resampler.process_partial_into_buffer(
+                Some(&self.pending),
+                &mut self.output_buffer,
+                None,
+            )?;
+            for channel in &mut self.pending {
+                channel.clear();
+            }
+            self.emit(produced, expected, output);
+        }
+
+        // Push silence through until the delayed tail has come out
+        while self.output_frames < expected {
+            let (_, produced) =
+                self.resampler
+                    .process_partial_into_buffer(None, &mut self.output_buffer, None)?;
+            if produced == 0 {
+                break;
+            }
+            self.emit(produced, expected, output);
+        }
+
+        debug!(
+            "Resampler finished: {} input frames -> {} output frames",
+            self.input_frames, self.output_frames
+        );
+
+        Ok(())
+    }
+
+    /// Interleaves `produced` frames from the output buffer into `output`,
+    /// skipping the delay and stopping at `limit` frames in total.
+    fn emit(&mut self, produced: usize, limit: usize, output: &mut Vec<f32>) {
+        let skip = self.delay_remaining.min(produced);
+        self.delay_remaining -= skip;
+
+        let take = (produced - skip).min(limit.saturating_sub(self.output_frames));
+        output.reserve(take * self.channels);
+        for frame_idx in skip..skip + take {
+            for channel in &self.output_buffer {
+                output.push(channel[frame_idx]);
+            }
+        }
+        self.output_frames += take;
+    }
+}
+
+/// Resamples interleaved audio to 16kHz. Audio already at 16kHz is passed
+/// through without a copy.
 pub fn resample_to_16khz(
     audio_data: &[f32],
     sample_rate: u32,
     channels: usize,
-) -> Result<Vec<f32>> {
+    quality: ResampleQuality,
+) -> Result<Cow<'_, [f32]>> {
     debug!(
-        "Resampling audio: {} samples, {}Hz -> 16kHz, {} channels",
+        "Resampling audio: {} samples, {}Hz -> 16kHz, {} channels, {} quality",
         audio_data.len(),
         sample_rate,
-        channels
+        channels,
+        quality
     );
 
     if sample_rate == 16000 {
         debug!("Audio is already at 16kHz, returning original data");
-        return Ok(audio_data.to_vec());
+        return Ok(Cow::Borrowed(audio_data));
     }
 
     let frames = audio_data.len() / channels;
@@ -25,66 +296,118 @@ pub fn resample_to_16khz(
         return Err(anyhow::anyhow!("No audio frames to resample"));
     }
 
-    debug!("Processing {frames} frames ({frames} samples per channel)");
+    debug!("Processing {frames} frames in chunks of {CHUNK_FRAMES}");
 
-    let params = rubato::SincInterpolationParameters {
-        sinc_len: 128,
-        f_cutoff: 0.95,
-        interpolation: SincInterpolationType::Linear,
-        oversampling_factor: 256,
-        window: WindowFunction::BlackmanHarris2,
-    };
+    let mut resampler = StreamingResampler::new(sample_rate, 16000, channels, quality)?;
+    let expected_output_frames = (frames as f64 * resampler.ratio) as usize;
+    let mut output = Vec::with_capacity(expected_output_frames * channels);
 
-    debug!("Resampler parameters: sinc_len=128, f_cutoff=0.95, interpolation=Linear");
+    let process_start = std::time::Instant::now();
+    resampler.process_into(audio_data, &mut output)?;
+    resampler.finish(&mut output)?;
+    let process_duration = process_start.elapsed();
 
-    let mut input_channels = vec![Vec::with_capacity(frames); channels];
-    for frame_idx in 0..frames {
-        for ch in 0..channels {
-            input_channels[ch].push(audio_data[frame_idx * channels + ch]);
-        }
-    }
+    let audio_seconds = frames as f64 / sample_rate as f64;
+    info!(
+        "Resampling complete ({}): {}Hz -> 16kHz, {} -> {} samples ({} frames), processed in {:?} ({:.0}x realtime, {:.2}ms per audio second)",
+        quality,
+        sample_rate,
+        audio_data.len(),
+        output.len(),
+        output.len() / channels,
+        process_duration,
+        audio_seconds / process_duration.as_secs_f64().max(1e-9),
+        process_duration.as_secs_f64() * 1000.0 / audio_seconds
+    );
 
-    debug!("Prepared {channels} input channels with {frames} samples each");
+    Ok(Cow::Owned(output))
+}
 
-    let resample_ratio = 16000.0 / sample_rate as f64;
-    debug!("Resample ratio: {resample_ratio:.6} ({sample_rate}Hz -> 16kHz)");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let resampler_start = std::time::Instant::now();
-    let mut resampler = SincFixedIn::<f32>::new(resample_ratio, 2.0, params, frames, channels)?;
-    debug!("Created resampler in {:?}", resampler_start.elapsed());
+    const QUALITIES: [ResampleQuality; 3] = [
+        ResampleQuality::Fast,
+        ResampleQuality::Balanced,
+        ResampleQuality::High,
+    ];
 
-    let process_start = std::time::Instant::now();
-    let resampled_channels = resampler.process(&input_channels, None)?;
-    let process_duration = process_start.elapsed();
+    fn tone(rate: u32, seconds: f32) -> Vec<f32> {
+        let len = (rate as f32 * seconds) as usize;
+        (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / rate as f32).sin() * 0.5)
+            .collect()
+    }
 
-    let delay = resampler.output_delay();
-    let expected_output_frames = (frames as f64 * resample_ratio) as usize;
+    #[test]
+    fn output_length_matches_ratio() {
+        for quality in QUALITIES {
+            for rate in [8000, 22050, 44100, 48000] {
+                let input = tone(rate, 1.3);
+                let output = resample_to_16khz(&input, rate, 1, quality).unwrap();
+                let expected = input.len() * 16000 / rate as usize;
+                assert!(
+                    output.len().abs_diff(expected) <= 2,
+                    "{quality} at {rate}Hz: {} samples, expected {expected}",
+                    output.len()
+                );
+            }
+        }
+    }
 
-    debug!(
-        "Resampling completed in {process_duration:?}: delay={delay} frames, expected_output={expected_output_frames} frames"
-    );
+    #[test]
+    fn delay_is_trimmed_from_the_start() {
+        // Half a second of silence, then a tone: the onset must stay at 0.5s
+        for quality in QUALITIES {
+            for rate in [8000, 48000] {
+                let mut input = vec![0.0; rate as usize / 2];
+                input.extend(tone(rate, 0.5));
+                let output = resample_to_16khz(&input, rate, 1, quality).unwrap();
+                let onset = output.iter().position(|s| s.abs() > 0.25).unwrap();
+                assert!(
+                    onset.abs_diff(8000) <= 8,
+                    "{quality} at {rate}Hz: onset at {onset}, expected 8000"
+                );
+            }
+        }
+    }
 
-    let mut output = Vec::with_capacity(expected_output_frames * channels);
-    let start_frame = delay;
-    let end_frame = (delay + expected_output_frames).min(resampled_channels[0].len());
+    #[test]
+    fn uneven_chunks_match_one_shot() {
+        for quality in QUALITIES {
+            // Stereo, so chunk boundaries also have to respect frames
+            let input: Vec<f32> = tone(44100, 0.7).into_iter().flat_map(|s| [s, -s]).collect();
+            let one_shot = resample_to_16khz(&input, 44100, 2, quality).unwrap();
 
-    debug!("Extracting frames {start_frame}-{end_frame} from resampled output");
+            let mut resampler = StreamingResampler::new(44100, 16000, 2, quality).unwrap();
+            let mut streamed = Vec::new();
+            let mut offset = 0;
+            for frames in [1, 1500, 3, 77, 2048, 5].iter().cycle() {
+                if offset >= input.len() {
+                    break;
+                }
+                let end = (offset + frames * 2).min(input.len());
+                resampler
+                    .process_into(&input[offset..end], &mut streamed)
+                    .unwrap();
+                offset = end;
+            }
+            resampler.finish(&mut streamed).unwrap();
 
-    for frame_idx in start_frame..end_frame {
-        for resampled_channel in resampled_channels.iter().take(channels) {
-            output.push(resampled_channel[frame_idx]);
+            assert_eq!(streamed.len(), one_shot.len(), "{quality}");
+            for (a, b) in streamed.iter().zip(one_shot.iter()) {
+                assert!((a - b).abs() < 1e-5, "{quality}: {a} != {b}");
+            }
         }
     }
 
-    let actual_output_frames = (end_frame - start_frame) * channels;
-    info!(
-        "Resampling complete: {}Hz -> 16kHz, {} -> {} samples ({} frames), processed in {:?}",
-        sample_rate,
-        audio_data.len(),
-        actual_output_frames,
-        end_frame - start_frame,
-        process_duration
-    );
-
-    Ok(output)
+    #[test]
+    fn audio_at_16khz_is_borrowed() {
+        let input = tone(16000, 0.1);
+        for quality in QUALITIES {
+            let output = resample_to_16khz(&input, 16000, 1, quality).unwrap();
+            assert!(matches!(output, Cow::Borrowed(_)));
+        }
+    }
 }
@@ -2,10 +2,28 @@
 
 use anyhow::Result;
 use log::{debug, error, info, warn};
-use std::sync::{Arc, Mutex};
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+use whisper_rs::{WhisperContext, WhisperContextParameters};
 
+use crate::whisper::chunking::{offset_segments, plan_windows, stitch_segments};
 use crate::whisper::config::WhisperConfig;
+use crate::whisper::decoding::{DecodingParams, DecodingStrategy};
+use crate::whisper::denoise::denoise;
+use crate::whisper::downmix::{downmix_to_mono, validate_layout, DownmixStrategy};
+use crate::whisper::pool::StatePool;
+use crate::whisper::preprocess::{preprocess, Normalization, PreprocessParams};
+use crate::whisper::resampler::ResampleQuality;
+use crate::whisper::vad::{detect_speech, SpeechMap};
+
+/// Language detection looks at the first 30 seconds, one whisper window.
+const LANGUAGE_DETECTION_SAMPLES: usize = 30 * 16000;
+
+/// Characters of the previous window's text carried into the next window's
+/// prompt. whisper keeps at most half its text context (224 tokens) of
+/// prompt, so the tail is kept short to leave room for the vocabulary.
+const PROMPT_CONTEXT_CHARS: usize = 400;
 
 pub struct InputAudio<'a> {
     pub data: &'a [f32],
@@ -13,9 +31,129 @@ pub struct InputAudio<'a> {
     pub channels: usize,
 }
 
+/// Per-request overrides of the transcriber's `WhisperConfig`. `None`
+/// fields fall back to the configured value.
+#[derive(Clone, Debug, Default)]
+pub struct TranscribeOptions {
+    /// Language code, or `auto` to detect it from the audio.
+    pub language: Option<String>,
+    pub task: Task,
+    /// Replaces the configured initial prompt.
+    pub initial_prompt: Option<String>,
+    /// Added to the configured vocabulary hints.
+    pub vocabulary: Vec<String>,
+    pub decoding_strategy: Option<DecodingStrategy>,
+    pub beam_size: Option<i32>,
+    pub best_of: Option<i32>,
+    pub temperature: Option<f32>,
+    pub temperature_inc: Option<f32>,
+    pub entropy_threshold: Option<f32>,
+    pub logprob_threshold: Option<f32>,
+    pub downmix: Option<DownmixStrategy>,
+    pub vad: Option<bool>,
+    pub dc_removal: Option<bool>,
+    pub highpass: Option<bool>,
+    pub normalize: Option<Normalization>,
+    pub limiter: Option<bool>,
+    pub denoise: Option<bool>,
+    pub denoise_strength: Option<f32>,
+    pub resample_quality: Option<ResampleQuality>,
+    /// Collect per-word timings from whisper's token timestamps.
+    pub word_timestamps: bool,
+    /// Keep every decoded token with its id, probabilities and timing.
+    pub verbose: bool,
+    pub channel_mode: ChannelMode,
+    /// Labels attached to segments in `ChannelMode::Split`, by channel index.
+    /// Channels without a label, or with a blank one, are tagged `channel_<n>`.
+    pub speaker_labels: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelMode {
+    /// Downmix all channels and transcribe once.
+    #[default]
+    Mix,
+    /// Transcribe every channel on its own and merge the segments into one
+    /// timeline, e.g. agent and customer on the two sides of a call.
+    Split,
+}
+
+impl std::fmt::Display for ChannelMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mix => f.write_str("mix"),
+            Self::Split => f.write_str("split"),
+        }
+    }
+}
+
+impl std::str::FromStr for ChannelMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mix" | "mixed" => Ok(Self::Mix),
+            "split" | "per_channel" => Ok(Self::Split),
+            _ => Err(format!("Invalid channel mode: {s} (expected mix or split)")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Task {
+    /// Transcribe in the spoken language.
+    #[default]
+    Transcribe,
+    /// Translate the speech into English text. Needs a multilingual model.
+    Translate,
+}
+
+impl std::fmt::Display for Task {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transcribe => f.write_str("transcribe"),
+            Self::Translate => f.write_str("translate"),
+        }
+    }
+}
+
+impl std::str::FromStr for Task {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "transcribe" => Ok(Self::Transcribe),
+            "translate" => Ok(Self::Translate),
+            _ => Err(format!(
+                "Invalid task: {s} (expected transcribe or translate)"
+            )),
+        }
+    }
+}
+
 pub struct TranscribeOutput {
     pub combined: String,
     pub segments: Vec<Segment>,
+    /// Set when the language was detected rather than configured.
+    pub language: Option<LanguageDetection>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LanguageDetection {
+    pub language: String,
+    pub probability: f32,
+    /// Most likely languages with their probabilities, best first.
+    pub candidates: Vec<(String, f32)>,
+}
+
+/// Whether `language` is `auto` or a language code whisper knows.
+pub fn is_supported_language(language: &str) -> bool {
+    language == "auto"
+        || (!language.is_empty()
+            && language.chars().all(|c| c.is_ascii_alphabetic())
+            && whisper_rs::get_lang_id(language).is_some())
 }
 
 #[derive(Clone)]
@@ -24,24 +162,56 @@ pub struct Segment {
     pub end: usize,
     pub text: String,
     pub confidence: f32,
+    pub channel: Option<usize>,
+    pub speaker: Option<String>,
+    /// Empty unless word timestamps were requested.
+    pub words: Vec<Word>,
+    /// Empty unless verbose output was requested.
+    pub tokens: Vec<Token>,
+}
+
+/// A decoded token as whisper reports it, timed in 10ms units.
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub text: String,
+    pub id: i32,
+    pub p: f32,
+    pub plog: f32,
+    pub t0: usize,
+    pub t1: usize,
+    /// Control and timestamp tokens (id at or above end-of-text), which are
+    /// not part of the transcript.
+    pub special: bool,
+}
+
+/// A word assembled from one or more sub-word tokens, timed in the same
+/// 10ms units as segments.
+#[derive(Clone, Debug)]
+pub struct Word {
+    pub word: String,
+    pub start: usize,
+    pub end: usize,
+    /// Mean probability of the word's tokens.
+    pub probability: f32,
 }
 
 impl PartialEq for Segment {
     fn eq(&self, other: &Self) -> bool {
-        self.start == other.start && self.end == other.end && self.text == other.text
+        self.start == other.start
+            && self.end == other.end
+            && self.text == other.text
+            && self.channel == other.channel
     }
 }
 
+/// Cheap to clone: clones share the loaded model and its pool of states.
 #[derive(Clone)]
 pub struct SimpleTranscriber {
-    inner: Arc<Mutex<TranscriberInner>>,
+    ctx: Arc<WhisperContext>,
+    pool: Arc<StatePool>,
     config: WhisperConfig,
 }
 
-struct TranscriberInner {
-    ctx: WhisperContext,
-}
-
 impl SimpleTranscriber {
     pub fn new(config: WhisperConfig) -> Result<Self> {
         info!(
@@ -68,15 +238,47 @@ impl SimpleTranscriber {
             config.model_path
         );
 
-        let inner = TranscriberInner { ctx };
+        let pool = StatePool::new(&ctx, config.pool_size)?;
+        info!(
+            "Transcriber ready: {} parallel states, {} threads each",
+            pool.size(),
+            config.threads_per_state()
+        );
 
         Ok(Self {
-            inner: Arc::new(Mutex::new(inner)),
+            ctx: Arc::new(ctx),
+            pool: Arc::new(pool),
             config,
         })
     }
 
-    pub fn transcribe(&self, audio_data: &InputAudio) -> Result<TranscribeOutput> {
+    /// Whether the loaded model handles languages other than English, and
+    /// so can translate. `.en` models cannot.
+    pub fn is_multilingual(&self) -> bool {
+        self.ctx.is_multilingual()
+    }
+
+    /// Settings the transcriber was created with, before per-request
+    /// overrides.
+    pub fn config(&self) -> &WhisperConfig {
+        &self.config
+    }
+
+    /// The loaded model, for reading its hyperparameters.
+    pub fn context(&self) -> &WhisperContext {
+        &self.ctx
+    }
+
+    /// Number of transcriptions this model can run at once.
+    pub fn pool_size(&self) -> usize {
+        self.pool.size()
+    }
+
+    pub fn transcribe(
+        &self,
+        audio_data: &InputAudio,
+        options: &TranscribeOptions,
+    ) -> Result<TranscribeOutput> {
         let start_time = std::time::Instant::now();
 
         debug!(
@@ -86,72 +288,551 @@ impl SimpleTranscriber {
             audio_data.channels
         );
 
-        // Resample audio to 16kHz if needed
-        let resampled_audio = if audio_data.sample_rate != 16000 {
-            info!(
-                "Resampling audio from {}Hz to 16kHz",
-                audio_data.sample_rate
+        validate_layout(audio_data.data, audio_data.channels)?;
+
+        if options.task == Task::Translate && !self.is_multilingual() {
+            error!(
+                "Translation requested with English-only model {:?}",
+                self.config.model_path
             );
-            crate::whisper::resampler::resample_to_16khz(
-                audio_data.data,
-                audio_data.sample_rate,
-                audio_data.channels,
-            )?
-        } else {
+            return Err(anyhow::anyhow!(
+                "Translation requires a multilingual model, {:?} is English-only",
+                self.config.model_path
+            ));
+        }
+
+        let output = match options.channel_mode {
+            ChannelMode::Mix => {
+                // Fold all channels into mono first, so preprocessing and
+                // resampling only run once
+                let downmix = options.downmix.unwrap_or(self.config.downmix);
+                let mono_audio = downmix_to_mono(audio_data.data, audio_data.channels, downmix)?;
+
+                debug!(
+                    "Audio downmixed to mono using {downmix}: {} samples",
+                    mono_audio.len()
+                );
+
+                let prepared = self.prepare_mono(&mono_audio, audio_data.sample_rate, options)?;
+                self.transcribe_mono(&prepared, options)?
+            }
+            ChannelMode::Split => self.transcribe_channels(audio_data, options)?,
+        };
+
+        let total_duration = start_time.elapsed();
+        let audio_duration_seconds = audio_data.data.len() as f64
+            / audio_data.channels as f64
+            / audio_data.sample_rate as f64;
+        let real_time_factor = audio_duration_seconds / total_duration.as_secs_f64();
+
+        info!(
+            "Transcription complete: {} segments, {} characters, {:.1}s audio processed in {:?} (RTF: {:.2}x)",
+            output.segments.len(),
+            output.combined.len(),
+            audio_duration_seconds,
+            total_duration,
+            real_time_factor
+        );
+
+        Ok(output)
+    }
+
+    /// Runs preprocessing and resampling over mono audio at its original
+    /// rate, borrowing the input when neither stage has anything to do.
+    fn prepare_mono<'a>(
+        &self,
+        mono_audio: &'a [f32],
+        sample_rate: u32,
+        options: &TranscribeOptions,
+    ) -> Result<Cow<'a, [f32]>> {
+        // Clean up the signal at its original rate, before resampling
+        let preprocessed = preprocess(mono_audio, 1, sample_rate, &self.preprocess_params(options));
+
+        if sample_rate == 16000 {
             debug!("Audio already at 16kHz, skipping resampling");
-            audio_data.data.to_vec()
+            return Ok(preprocessed);
+        }
+
+        info!("Resampling audio from {sample_rate}Hz to 16kHz");
+        let resampled = crate::whisper::resampler::resample_to_16khz(
+            &preprocessed,
+            sample_rate,
+            1,
+            options
+                .resample_quality
+                .unwrap_or(self.config.resample_quality),
+        )?;
+
+        debug!("Audio after resampling: {} samples", resampled.len());
+
+        Ok(Cow::Owned(resampled.into_owned()))
+    }
+
+    /// Merges the configured decoder settings with per-request overrides.
+    fn decoding_params(&self, options: &TranscribeOptions) -> DecodingParams {
+        let configured = self.config.decoding_params();
+        DecodingParams {
+            strategy: options.decoding_strategy.unwrap_or(configured.strategy),
+            beam_size: options.beam_size.unwrap_or(configured.beam_size),
+            best_of: options.best_of.unwrap_or(configured.best_of),
+            temperature: options.temperature.unwrap_or(configured.temperature),
+            temperature_inc: options
+                .temperature_inc
+                .unwrap_or(configured.temperature_inc),
+            entropy_threshold: options
+                .entropy_threshold
+                .unwrap_or(configured.entropy_threshold),
+            logprob_threshold: options
+                .logprob_threshold
+                .unwrap_or(configured.logprob_threshold),
+        }
+    }
+
+    /// Merges the configured preprocessing stages with per-request overrides.
+    fn preprocess_params(&self, options: &TranscribeOptions) -> PreprocessParams {
+        let highpass = options.highpass.unwrap_or(self.config.preprocess_highpass);
+        let normalize = options
+            .normalize
+            .unwrap_or(self.config.preprocess_normalize);
+        // The configured target is in the units of the configured mode, a
+        // request switching modes gets that mode's own default instead
+        let target_db = match self.config.preprocess_target_db {
+            Some(target) if normalize == self.config.preprocess_normalize => target,
+            _ => normalize.default_target_db(),
         };
+        PreprocessParams {
+            dc_removal: options
+                .dc_removal
+                .unwrap_or(self.config.preprocess_dc_removal),
+            highpass_hz: highpass.then_some(self.config.preprocess_highpass_hz),
+            normalize,
+            target_db,
+            limiter: options.limiter.unwrap_or(self.config.preprocess_limiter),
+        }
+    }
+
+    /// Transcribes every channel independently and merges the results into
+    /// a single timeline, tagging each segment with its channel and label.
+    fn transcribe_channels(
+        &self,
+        audio_data: &InputAudio,
+        options: &TranscribeOptions,
+    ) -> Result<TranscribeOutput> {
+        let channels = audio_data.channels;
+        let mut segments = Vec::new();
+        let mut language = None;
+
+        for channel in 0..channels {
+            let label = options
+                .speaker_labels
+                .get(channel)
+                .map(|label| label.trim())
+                .filter(|label| !label.is_empty())
+                .map_or_else(|| format!("channel_{channel}"), str::to_string);
+            info!("Transcribing channel {channel} ({label}) of {channels}");
+
+            let channel_audio =
+                downmix_to_mono(audio_data.data, channels, DownmixStrategy::Channel(channel))?;
+            let prepared = self.prepare_mono(&channel_audio, audio_data.sample_rate, options)?;
+            let output = self.transcribe_mono(&prepared, options)?;
+
+            if language.is_none() {
+                language = output.language;
+            }
+            segments.extend(output.segments.into_iter().map(|mut segment| {
+                segment.channel = Some(channel);
+                segment.speaker = Some(label.clone());
+                segment
+            }));
+        }
+
+        segments.sort_by_key(|segment| (segment.start, segment.channel));
+
+        let combined = segments
+            .iter()
+            .map(|segment| {
+                format!(
+                    "{}: {}",
+                    segment.speaker.as_deref().unwrap_or_default(),
+                    segment.text.trim()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
 
-        debug!("Audio after resampling: {} samples", resampled_audio.len());
+        debug!(
+            "Merged {} segments from {} channels into one timeline",
+            segments.len(),
+            channels
+        );
 
-        if resampled_audio.len() < 16000 {
+        Ok(TranscribeOutput {
+            combined,
+            segments,
+            language,
+        })
+    }
+
+    fn transcribe_mono(
+        &self,
+        mono_audio: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscribeOutput> {
+        if mono_audio.len() < 16000 {
             warn!(
                 "Audio is too short: {} samples (less than 1 second)",
-                resampled_audio.len()
+                mono_audio.len()
             );
             return Err(anyhow::anyhow!("Audio is too short (less than 1 second)"));
         }
 
-        // Convert to mono
-        debug!("Converting stereo audio to mono");
-        let mono_audio =
-            whisper_rs::convert_stereo_to_mono_audio(&resampled_audio).map_err(|e| {
-                error!("Failed to convert audio to mono: {e}");
-                anyhow::anyhow!("Failed to convert audio to mono: {e}")
-            })?;
+        // Suppress steady background noise before VAD and inference see it
+        let denoised = if options.denoise.unwrap_or(self.config.denoise) {
+            let strength = options
+                .denoise_strength
+                .unwrap_or(self.config.denoise_strength);
+            denoise(mono_audio, strength)
+        } else {
+            Cow::Borrowed(mono_audio)
+        };
+        let mono_audio: &[f32] = &denoised;
+
+        self.transcribe_speech_regions(mono_audio, options)
+    }
+
+    fn language_for<'a>(&'a self, options: &'a TranscribeOptions) -> &'a str {
+        options.language.as_deref().unwrap_or(&self.config.language)
+    }
+
+    /// Runs whisper's language identification over the first 30 seconds.
+    /// Returns `None` when detection fails, leaving whisper to detect the
+    /// language on its own during decoding.
+    fn detect_language(&self, mono_audio: &[f32]) -> Option<LanguageDetection> {
+        let sample = &mono_audio[..mono_audio.len().min(LANGUAGE_DETECTION_SAMPLES)];
+        let threads = self.config.threads_per_state() as usize;
+        let start = std::time::Instant::now();
+
+        if !self.is_multilingual() {
+            debug!("Model is English-only, skipping language detection");
+            return Some(LanguageDetection {
+                language: "en".to_string(),
+                probability: 1.0,
+                candidates: vec![("en".to_string(), 1.0)],
+            });
+        }
+
+        let mut state = match self.pool.acquire() {
+            Ok(state) => state,
+            Err(e) => {
+                error!("No whisper state for language detection: {e}");
+                return None;
+            }
+        };
+        let detected = state
+            .pcm_to_mel(sample, threads)
+            .and_then(|()| state.lang_detect(0, threads));
+        drop(state);
+
+        let (language_id, probabilities) = match detected {
+            Ok(detected) => detected,
+            Err(e) => {
+                warn!("Language detection failed, letting whisper detect during decoding: {e}");
+                return None;
+            }
+        };
+
+        let mut candidates: Vec<(String, f32)> = probabilities
+            .iter()
+            .enumerate()
+            .filter_map(|(id, &probability)| {
+                whisper_rs::get_lang_str(id as i32).map(|code| (code.to_string(), probability))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates.truncate(self.config.language_top_n.max(1));
+
+        let language = whisper_rs::get_lang_str(language_id)?.to_string();
+        let probability = probabilities
+            .get(language_id as usize)
+            .copied()
+            .unwrap_or_default();
+
+        info!(
+            "Detected language {language} (p={probability:.3}) from {:.1}s of audio in {:?}",
+            sample.len() as f64 / 16000.0,
+            start.elapsed()
+        );
+        debug!("Language candidates: {candidates:?}");
+
+        Some(LanguageDetection {
+            language,
+            probability,
+            candidates,
+        })
+    }
+
+    /// Transcribes mono audio, restricted to detected speech when VAD is on.
+    fn transcribe_speech_regions(
+        &self,
+        mono_audio: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscribeOutput> {
+        if !options.vad.unwrap_or(self.config.vad) {
+            return self.transcribe_in_language(mono_audio, options);
+        }
+
+        // Only send detected speech to whisper, it tends to hallucinate on
+        // long silent stretches
+        let regions = detect_speech(mono_audio, &self.config.vad_params());
+        if regions.is_empty() {
+            info!("VAD found no speech, skipping inference");
+            return Ok(TranscribeOutput {
+                combined: String::new(),
+                segments: Vec::new(),
+                language: None,
+            });
+        }
+
+        let (mut speech_audio, speech_map) = SpeechMap::compact(mono_audio, &regions);
+        info!(
+            "VAD kept {:.1}s of speech out of {:.1}s",
+            speech_audio.len() as f64 / 16000.0,
+            mono_audio.len() as f64 / 16000.0
+        );
+
+        // A single short utterance still has to satisfy the minimum length
+        if speech_audio.len() < 16000 {
+            speech_audio.resize(16000, 0.0);
+        }
+
+        // Language detection sees the speech too, rather than whatever
+        // silence or noise opens the recording
+        let mut output = self.transcribe_in_language(&speech_audio, options)?;
+        speech_map.remap_segments(&mut output.segments);
+        Ok(output)
+    }
+
+    /// Transcribes mono audio, detecting its language first when set to auto.
+    fn transcribe_in_language(
+        &self,
+        mono_audio: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscribeOutput> {
+        // Detect the language once up front, so every window is transcribed
+        // in the same language
+        let detection = if self.language_for(options) == "auto" {
+            self.detect_language(mono_audio)
+        } else {
+            None
+        };
+        let detected_options;
+        let options = match &detection {
+            Some(detection) => {
+                detected_options = TranscribeOptions {
+                    language: Some(detection.language.clone()),
+                    ..options.clone()
+                };
+                &detected_options
+            }
+            None => options,
+        };
+
+        let mut output = self.transcribe_speech(mono_audio, options)?;
+        output.language = detection;
+        Ok(output)
+    }
+
+    fn transcribe_speech(
+        &self,
+        mono_audio: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscribeOutput> {
+        let window_samples = (self.config.chunk_seconds.max(0.0) * 16000.0) as usize;
+        let overlap_samples = (self.config.chunk_overlap_seconds.max(0.0) * 16000.0) as usize;
+
+        if window_samples > 0 && mono_audio.len() > window_samples + overlap_samples {
+            self.transcribe_long_form(mono_audio, window_samples, overlap_samples, options)
+        } else {
+            self.run_whisper(mono_audio, options, None)
+        }
+    }
+
+    /// Transcribes long recordings in overlapping windows so a single
+    /// failure or a huge buffer does not sink the whole request. Segment
+    /// times are shifted back onto the global timeline and the overlaps are
+    /// de-duplicated.
+    fn transcribe_long_form(
+        &self,
+        mono_audio: &[f32],
+        window_samples: usize,
+        overlap_samples: usize,
+        options: &TranscribeOptions,
+    ) -> Result<TranscribeOutput> {
+        let windows = plan_windows(mono_audio, window_samples, overlap_samples);
+        info!(
+            "Long-form transcription: {:.1}s audio in {} windows",
+            mono_audio.len() as f64 / 16000.0,
+            windows.len()
+        );
+
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut previous_window: Option<std::ops::Range<usize>> = None;
+        let mut previous_text: Option<String> = None;
+        let mut failed_windows = 0;
+
+        for (index, window) in windows.iter().enumerate() {
+            debug!(
+                "Transcribing window {}/{}: samples {}-{}",
+                index + 1,
+                windows.len(),
+                window.start,
+                window.end
+            );
+
+            let result = self.run_whisper(
+                &mono_audio[window.clone()],
+                options,
+                previous_text.as_deref(),
+            );
+            let mut output = match result {
+                Ok(output) => output,
+                Err(e) => {
+                    warn!(
+                        "Window {}/{} ({:.1}s-{:.1}s) failed, skipping: {e}",
+                        index + 1,
+                        windows.len(),
+                        window.start as f64 / 16000.0,
+                        window.end as f64 / 16000.0
+                    );
+                    failed_windows += 1;
+                    previous_window = None;
+                    previous_text = None;
+                    continue;
+                }
+            };
+
+            offset_segments(&mut output.segments, window.start);
+
+            match &previous_window {
+                Some(previous) if previous.end > window.start => {
+                    stitch_segments(&mut segments, output.segments, window.start..previous.end)
+                }
+                _ => segments.extend(output.segments),
+            }
+            previous_window = Some(window.clone());
+            previous_text = Some(output.combined);
+        }
+
+        if failed_windows == windows.len() {
+            error!("All {} windows failed to transcribe", windows.len());
+            return Err(anyhow::anyhow!(
+                "Transcription failed for all {} windows",
+                windows.len()
+            ));
+        }
+        if failed_windows > 0 {
+            warn!(
+                "{failed_windows} of {} windows failed, returning partial transcription",
+                windows.len()
+            );
+        }
+
+        let combined = segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect();
+
+        Ok(TranscribeOutput {
+            combined,
+            segments,
+            language: None,
+        })
+    }
 
-        debug!("Audio converted to mono: {} samples", mono_audio.len());
+    /// Builds whisper's initial prompt from the vocabulary hints, the
+    /// initial prompt and, in long-form mode, the tail of the previous
+    /// window's text. whisper keeps the end of an over-long prompt, so the
+    /// context closest to the audio goes last.
+    fn build_prompt(&self, options: &TranscribeOptions, context: Option<&str>) -> Option<String> {
+        let mut parts = Vec::new();
 
+        let vocabulary: Vec<&str> = self
+            .config
+            .vocabulary
+            .iter()
+            .chain(&options.vocabulary)
+            .map(|term| term.trim())
+            .filter(|term| !term.is_empty())
+            .collect();
+        if !vocabulary.is_empty() {
+            parts.push(format!("Glossary: {}.", vocabulary.join(", ")));
+        }
+
+        if let Some(prompt) = options
+            .initial_prompt
+            .as_deref()
+            .or(self.config.initial_prompt.as_deref())
+            .map(str::trim)
+            .filter(|prompt| !prompt.is_empty())
+        {
+            parts.push(prompt.to_string());
+        }
+
+        if let Some(context) = context.map(str::trim).filter(|text| !text.is_empty()) {
+            let skip = context.chars().count().saturating_sub(PROMPT_CONTEXT_CHARS);
+            let tail: String = context.chars().skip(skip).collect();
+            parts.push(tail.trim_start().to_string());
+        }
+
+        if parts.is_empty() {
+            return None;
+        }
+
+        // A NUL byte would not survive the trip into whisper's C string
+        Some(parts.join(" ").replace('\0', ""))
+    }
+
+    fn run_whisper(
+        &self,
+        mono_audio: &[f32],
+        options: &TranscribeOptions,
+        context: Option<&str>,
+    ) -> Result<TranscribeOutput> {
         // Configure transcription parameters
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-        params.set_language(Some(&self.config.language));
+        let language = self.language_for(options);
+        let decoding = self.decoding_params(options);
+        let mut params = decoding.full_params();
+        params.set_language(Some(language));
+
+        if let Some(prompt) = self.build_prompt(options, context) {
+            debug!("Initial prompt: {prompt:?}");
+            params.set_initial_prompt(&prompt);
+        }
         params.set_print_special(false);
         params.set_print_progress(false);
         params.set_print_realtime(false);
         params.set_print_timestamps(true);
         params.set_audio_ctx(self.config.audio_context);
         params.set_no_speech_thold(self.config.no_speech_threshold);
-        params.set_n_threads(self.config.num_threads);
+        params.set_n_threads(self.config.threads_per_state());
+        params.set_token_timestamps(options.word_timestamps || options.verbose);
+        params.set_translate(options.task == Task::Translate);
 
         debug!(
-            "Transcription parameters: language={}, audio_ctx={}, no_speech_threshold={}, threads={}",
-            self.config.language,
+            "Transcription parameters: task={}, decoding=[{}], language={}, audio_ctx={}, no_speech_threshold={}, threads={}, token_timestamps={}",
+            options.task,
+            decoding,
+            language,
             self.config.audio_context,
             self.config.no_speech_threshold,
-            self.config.num_threads
+            self.config.threads_per_state(),
+            options.word_timestamps
         );
 
-        // Lock the context and run transcription
-        let inner = self.inner.lock().map_err(|_| {
-            error!("Failed to acquire transcriber lock");
-            anyhow::anyhow!("Failed to acquire transcriber lock")
-        })?;
+        let token_eot = self.ctx.token_eot();
 
-        debug!("Acquired transcriber lock, creating whisper state");
-        let mut state = inner.ctx.create_state().map_err(|e| {
-            error!("Failed to create whisper state: {e}");
-            anyhow::anyhow!("Failed to create whisper state: {e}")
-        })?;
+        // Wait for a free state; other requests keep running on the rest
+        let mut state = self.pool.acquire()?;
 
         debug!(
             "Running whisper transcription on {} samples",
@@ -159,7 +840,7 @@ impl SimpleTranscriber {
         );
         let transcription_start = std::time::Instant::now();
 
-        state.full(params, &mono_audio).map_err(|e| {
+        state.full(params, mono_audio).map_err(|e| {
             error!("Failed to run transcription: {e}");
             anyhow::anyhow!("Failed to run transcription: {e}")
         })?;
@@ -197,6 +878,18 @@ impl SimpleTranscriber {
             // Calculate confidence from token probabilities
             let confidence = self.calculate_segment_confidence(&state, i)?;
 
+            let words = if options.word_timestamps {
+                self.extract_words(&state, i, token_eot)?
+            } else {
+                Vec::new()
+            };
+
+            let tokens = if options.verbose {
+                self.extract_tokens(&state, i, token_eot)?
+            } else {
+                Vec::new()
+            };
+
             debug!(
                 "Segment {}: {}ms-{}ms, confidence: {:.3}, text: {:?}",
                 i,
@@ -212,23 +905,123 @@ impl SimpleTranscriber {
                 end: end as usize,
                 text,
                 confidence,
+                channel: None,
+                speaker: None,
+                words,
+                tokens,
             });
         }
 
-        let total_duration = start_time.elapsed();
-        let audio_duration_seconds = mono_audio.len() as f64 / 16000.0;
-        let real_time_factor = audio_duration_seconds / total_duration.as_secs_f64();
+        Ok(TranscribeOutput {
+            combined,
+            segments,
+            language: None,
+        })
+    }
 
-        info!(
-            "Transcription complete: {} segments, {} characters, {:.1}s audio processed in {:?} (RTF: {:.2}x)",
-            segments.len(),
-            combined.len(),
-            audio_duration_seconds,
-            total_duration,
-            real_time_factor
+    /// Merges a segment's text tokens into words. A token starting with a
+    /// space opens a new word, any other token continues the current one.
+    /// Bytes are joined before decoding, since whisper may split a UTF-8
+    /// character across tokens.
+    fn extract_words(
+        &self,
+        state: &whisper_rs::WhisperState,
+        segment_idx: i32,
+        token_eot: i32,
+    ) -> Result<Vec<Word>> {
+        struct PendingWord {
+            bytes: Vec<u8>,
+            start: i64,
+            end: i64,
+            probability_sum: f32,
+            tokens: usize,
+        }
+
+        fn finish(pending: PendingWord, words: &mut Vec<Word>) {
+            let word = String::from_utf8_lossy(&pending.bytes).trim().to_string();
+            if !word.is_empty() {
+                words.push(Word {
+                    word,
+                    start: pending.start.max(0) as usize,
+                    end: pending.end.max(pending.start).max(0) as usize,
+                    probability: pending.probability_sum / pending.tokens as f32,
+                });
+            }
+        }
+
+        let n_tokens = state.full_n_tokens(segment_idx)?;
+        let mut words = Vec::new();
+        let mut current: Option<PendingWord> = None;
+
+        for token_idx in 0..n_tokens {
+            let token_data = state.full_get_token_data(segment_idx, token_idx)?;
+            // Special tokens ([_BEG_], timestamps, ...) carry no text
+            if token_data.id >= token_eot {
+                continue;
+            }
+            let bytes = state.full_get_token_bytes(segment_idx, token_idx)?;
+            if bytes.is_empty() {
+                continue;
+            }
+
+            let starts_word = bytes[0] == b' ';
+            match current.as_mut() {
+                Some(word) if !starts_word => {
+                    word.bytes.extend_from_slice(&bytes);
+                    word.end = token_data.t1;
+                    word.probability_sum += token_data.p;
+                    word.tokens += 1;
+                }
+                _ => {
+                    if let Some(previous) = current.take() {
+                        finish(previous, &mut words);
+                    }
+                    current = Some(PendingWord {
+                        bytes,
+                        start: token_data.t0,
+                        end: token_data.t1,
+                        probability_sum: token_data.p,
+                        tokens: 1,
+                    });
+                }
+            }
+        }
+        if let Some(last) = current {
+            finish(last, &mut words);
+        }
+
+        debug!(
+            "Segment {segment_idx}: merged {n_tokens} tokens into {} words",
+            words.len()
         );
 
-        Ok(TranscribeOutput { combined, segments })
+        Ok(words)
+    }
+
+    fn extract_tokens(
+        &self,
+        state: &whisper_rs::WhisperState,
+        segment_idx: i32,
+        token_eot: i32,
+    ) -> Result<Vec<Token>> {
+        let n_tokens = state.full_n_tokens(segment_idx)?;
+        let mut tokens = Vec::with_capacity(n_tokens as usize);
+
+        for token_idx in 0..n_tokens {
+            let token_data = state.full_get_token_data(segment_idx, token_idx)?;
+            let text = state.full_get_token_text_lossy(segment_idx, token_idx)?;
+            tokens.push(Token {
+                text,
+                id: token_data.id,
+                p: token_data.p,
+                plog: token_data.plog,
+                t0: token_data.t0.max(0) as usize,
+                t1: token_data.t1.max(0) as usize,
+                special: token_data.id >= token_eot,
+            });
+        }
+
+        Ok(tokens)
     }
 
     fn calculate_segment_confidence(
@@ -0,0 +1,257 @@
+use log::debug;
+use std::ops::Range;
+
+use crate::whisper::chunking::SAMPLES_PER_TIMESTAMP;
+use crate::whisper::transcriber::Segment;
+
+/// Analysis frame of 30ms at 16kHz.
+const FRAME_SAMPLES: usize = 480;
+
+/// Speech has to stand this far above the estimated noise floor.
+const NOISE_FLOOR_MARGIN_DB: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct VadParams {
+    /// Frames quieter than this are never speech, whatever the noise floor.
+    pub threshold_db: f32,
+    /// Pauses shorter than this do not split a speech region.
+    pub min_silence_ms: u32,
+    /// Regions shorter than this are dropped as clicks and pops.
+    pub min_speech_ms: u32,
+    /// Audio kept on both sides of every region so word edges survive.
+    pub padding_ms: u32,
+}
+
+/// Finds speech in 16kHz mono audio using frame energy against an adaptive
+/// noise floor, with zero-crossing rate to keep quiet unvoiced consonants.
+pub fn detect_speech(audio: &[f32], params: &VadParams) -> Vec<Range<usize>> {
+    let frames: Vec<(f32, f32)> = audio
+        .chunks(FRAME_SAMPLES)
+        .map(|frame| (frame_energy_db(frame), zero_crossing_rate(frame)))
+        .collect();
+
+    if frames.is_empty() {
+        return Vec::new();
+    }
+
+    let mut energies: Vec<f32> = frames.iter().map(|(db, _)| *db).collect();
+    energies.sort_by(|a, b| a.total_cmp(b));
+    let noise_floor = energies[energies.len() / 10];
+    let threshold = params.threshold_db.max(noise_floor + NOISE_FLOOR_MARGIN_DB);
+
+    debug!(
+        "VAD: {} frames, noise floor {:.1} dBFS, speech threshold {:.1} dBFS",
+        frames.len(),
+        noise_floor,
+        threshold
+    );
+
+    let is_speech = |&(db, zcr): &(f32, f32)| {
+        // Fricatives are quiet but noisy, give them a few dB of slack
+        db >= threshold || (db >= threshold - 6.0 && (0.1..0.5).contains(&zcr))
+    };
+
+    let ms_to_frames = |ms: u32| (ms as usize * 16).div_ceil(FRAME_SAMPLES);
+    let min_silence_frames = ms_to_frames(params.min_silence_ms);
+    let min_speech_frames = ms_to_frames(params.min_speech_ms);
+
+    // Collect raw speech runs in frame units, bridging short pauses
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for (index, frame) in frames.iter().enumerate() {
+        if !is_speech(frame) {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if index - run.end <= min_silence_frames => run.end = index + 1,
+            _ => runs.push(index..index + 1),
+        }
+    }
+    runs.retain(|run| run.len() >= min_speech_frames);
+
+    // Convert to padded sample ranges, merging any that now touch
+    let padding = params.padding_ms as usize * 16;
+    let mut regions: Vec<Range<usize>> = Vec::new();
+    for run in runs {
+        let start = (run.start * FRAME_SAMPLES).saturating_sub(padding);
+        let end = (run.end * FRAME_SAMPLES + padding).min(audio.len());
+        match regions.last_mut() {
+            Some(region) if start <= region.end => region.end = end,
+            _ => regions.push(start..end),
+        }
+    }
+
+    let speech_samples: usize = regions.iter().map(|r| r.len()).sum();
+    debug!(
+        "VAD found {} speech regions covering {:.1}s of {:.1}s",
+        regions.len(),
+        speech_samples as f64 / 16000.0,
+        audio.len() as f64 / 16000.0
+    );
+
+    regions
+}
+
+fn frame_energy_db(frame: &[f32]) -> f32 {
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+    10.0 * mean_square.max(1e-10).log10()
+}
+
+fn zero_crossing_rate(frame: &[f32]) -> f32 {
+    if frame.len() < 2 {
+        return 0.0;
+    }
+    let crossings = frame
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+        .count();
+    crossings as f32 / (frame.len() - 1) as f32
+}
+
+/// Maps positions in audio made of concatenated speech regions back to the
+/// original recording.
+pub struct SpeechMap {
+    /// (start in compacted audio, start in original audio, length)
+    spans: Vec<(usize, usize, usize)>,
+}
+
+impl SpeechMap {
+    /// Concatenates `regions` of `audio` and returns the compacted audio
+    /// along with the map back to the original timeline.
+    pub fn compact(audio: &[f32], regions: &[Range<usize>]) -> (Vec<f32>, Self) {
+        let total: usize = regions.iter().map(|r| r.len()).sum();
+        let mut compacted = Vec::with_capacity(total);
+        let mut spans = Vec::with_capacity(regions.len());
+
+        for region in regions {
+            spans.push((compacted.len(), region.start, region.len()));
+            compacted.extend_from_slice(&audio[region.clone()]);
+        }
+
+        (compacted, Self { spans })
+    }
+
+    /// Maps a sample position; `is_end` picks the span a position on a
+    /// boundary belongs to, so segment ends stay in the region they close.
+    fn to_original(&self, position: usize, is_end: bool) -> usize {
+        let span = self
+            .spans
+            .iter()
+            .rev()
+            .find(|(compact_start, _, _)| {
+                if is_end {
+                    *compact_start < position
+                } else {
+                    *compact_start <= position
+                }
+            })
+            .or(self.spans.first());
+
+        match span {
+            Some(&(compact_start, original_start, len)) => {
+                original_start + position.saturating_sub(compact_start).min(len)
+            }
+            None => position,
+        }
+    }
+
+    pub fn remap_segments(&self, segments: &mut [Segment]) {
+        for segment in segments {
+            segment.start = self.to_original(segment.start * SAMPLES_PER_TIMESTAMP, false)
+                / SAMPLES_PER_TIMESTAMP;
+            segment.end =
+                self.to_original(segment.end * SAMPLES_PER_TIMESTAMP, true) / SAMPLES_PER_TIMESTAMP;
+            for word in &mut segment.words {
+                word.start = self.to_original(word.start * SAMPLES_PER_TIMESTAMP, false)
+                    / SAMPLES_PER_TIMESTAMP;
+                word.end = self.to_original(word.end * SAMPLES_PER_TIMESTAMP, true)
+                    / SAMPLES_PER_TIMESTAMP;
+            }
+            for token in &mut segment.tokens {
+                token.t0 = self.to_original(token.t0 * SAMPLES_PER_TIMESTAMP, false)
+                    / SAMPLES_PER_TIMESTAMP;
+                token.t1 = self.to_original(token.t1 * SAMPLES_PER_TIMESTAMP, true)
+                    / SAMPLES_PER_TIMESTAMP;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whisper::transcriber::Word;
+
+    /// Speech at 0.1s-0.3s and 1.0s-1.2s of a 1.5s recording, where every
+    /// sample holds its own index.
+    fn compacted() -> (Vec<f32>, SpeechMap) {
+        let audio: Vec<f32> = (0..24000).map(|i| i as f32).collect();
+        SpeechMap::compact(&audio, &[1600..4800, 16000..19200])
+    }
+
+    fn segment(start: usize, end: usize) -> Segment {
+        Segment {
+            start,
+            end,
+            text: String::new(),
+            confidence: 1.0,
+            channel: None,
+            speaker: None,
+            words: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn compact_concatenates_regions() {
+        let (audio, _) = compacted();
+        assert_eq!(audio.len(), 6400);
+        assert_eq!(audio[0], 1600.0);
+        assert_eq!(audio[3199], 4799.0);
+        assert_eq!(audio[3200], 16000.0);
+        assert_eq!(audio[6399], 19199.0);
+    }
+
+    #[test]
+    fn position_in_first_region() {
+        let (_, map) = compacted();
+        assert_eq!(map.to_original(1000, false), 2600);
+        assert_eq!(map.to_original(1000, true), 2600);
+    }
+
+    #[test]
+    fn position_after_removed_gap() {
+        let (_, map) = compacted();
+        assert_eq!(map.to_original(4000, false), 16800);
+        assert_eq!(map.to_original(4000, true), 16800);
+    }
+
+    #[test]
+    fn boundary_belongs_to_the_region_it_opens_or_closes() {
+        let (_, map) = compacted();
+        assert_eq!(map.to_original(3200, false), 16000);
+        assert_eq!(map.to_original(3200, true), 4800);
+    }
+
+    #[test]
+    fn remap_moves_segments_and_words() {
+        let (_, map) = compacted();
+        // 0.05s-0.2s and 0.2s-0.3s of the compacted audio
+        let mut first = segment(5, 20);
+        first.words.push(Word {
+            word: "hello".to_string(),
+            start: 5,
+            end: 20,
+            probability: 1.0,
+        });
+        let mut segments = vec![first, segment(20, 30)];
+
+        map.remap_segments(&mut segments);
+
+        assert_eq!((segments[0].start, segments[0].end), (15, 30));
+        assert_eq!(
+            (segments[0].words[0].start, segments[0].words[0].end),
+            (15, 30)
+        );
+        assert_eq!((segments[1].start, segments[1].end), (100, 110));
+    }
+}